@@ -0,0 +1,560 @@
+use crate::bitboard::Bitboard;
+use crate::board::{Board, DEFAULT_BOARD};
+use crate::castle_direction::{CastleDirection, CastlingRooks};
+use crate::chess_move::{Move, MoveKind};
+use crate::fen::{FenError, Position};
+use crate::move_generation::Termination;
+use crate::move_list::MoveList;
+use crate::nnue::Network;
+use crate::piece_arrangement::PieceArrangement;
+use crate::piece_type::{NonKingPieceType, PieceType, PromotionPieceType};
+use crate::player::Player;
+use crate::position_builder::{PositionBuilder, PositionError};
+use crate::square::Square;
+
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+/// A move [`AnyBoard::apply_move`] refused because it is not legal in the position
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct IllegalMove(pub Move);
+
+impl Display for IllegalMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "illegal move {}", self.0)
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+/// Why a FEN could not be loaded into an [`AnyBoard`]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ParseBoardError {
+    Fen(FenError),
+    /// The FEN is well formed but the position is not one a game could reach
+    Position(PositionError),
+}
+
+impl Display for ParseBoardError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBoardError::Fen(err) => Display::fmt(err, f),
+            ParseBoardError::Position(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for ParseBoardError {}
+
+impl From<FenError> for ParseBoardError {
+    fn from(err: FenError) -> Self {
+        ParseBoardError::Fen(err)
+    }
+}
+
+impl From<PositionError> for ParseBoardError {
+    fn from(err: PositionError) -> Self {
+        ParseBoardError::Position(err)
+    }
+}
+
+/// Declare [`AnyBoard`] with one variant per `Board` instantiation, the conversions into it, and
+/// `dispatch!` to run an expression against whichever concrete board is inside
+///
+/// Boards without and with an en passant pawn come as two lists, so that `dispatch_en_passant!`
+/// can reach the transitions only the latter have.
+macro_rules! any_board {
+    (
+        $($variant:ident => <$white:literal, false, $wk:literal, $wq:literal, $bk:literal, $bq:literal>,)*
+        ;
+        $($ep_variant:ident => <$ep_white:literal, true, $ep_wk:literal, $ep_wq:literal, $ep_bk:literal, $ep_bq:literal>,)*
+    ) => {
+        /// A [`Board`] whose side to move, en passant pawn and castling rights are only known at
+        /// runtime
+        ///
+        /// Every method dispatches once on the variant and then runs the monomorphised code for
+        /// that exact type-state. Variants are named by side to move, `Ep` when a pawn may be
+        /// captured en passant, then the castling rights as in FEN.
+        #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+        pub enum AnyBoard {
+            $($variant(Board<$white, false, $wk, $wq, $bk, $bq>),)*
+            $($ep_variant(Board<$ep_white, true, $ep_wk, $ep_wq, $ep_bk, $ep_bq>),)*
+        }
+
+        $(
+            impl From<Board<$white, false, $wk, $wq, $bk, $bq>> for AnyBoard {
+                fn from(board: Board<$white, false, $wk, $wq, $bk, $bq>) -> Self {
+                    AnyBoard::$variant(board)
+                }
+            }
+        )*
+
+        $(
+            impl From<Board<$ep_white, true, $ep_wk, $ep_wq, $ep_bk, $ep_bq>> for AnyBoard {
+                fn from(board: Board<$ep_white, true, $ep_wk, $ep_wq, $ep_bk, $ep_bq>) -> Self {
+                    AnyBoard::$ep_variant(board)
+                }
+            }
+        )*
+
+        impl From<Position> for AnyBoard {
+            fn from(position: Position) -> Self {
+                let rights = |player, direction| position.has_castle_rights(player, direction);
+                match (
+                    position.side_to_move().is_white(),
+                    position.en_passant_square().is_some(),
+                    rights(Player::White, CastleDirection::KingSide),
+                    rights(Player::White, CastleDirection::QueenSide),
+                    rights(Player::Black, CastleDirection::KingSide),
+                    rights(Player::Black, CastleDirection::QueenSide),
+                ) {
+                    $(
+                        ($white, false, $wk, $wq, $bk, $bq) => AnyBoard::$variant(
+                            Board::new(*position.pieces(), position.en_passant_square())
+                                .with_clocks(position.halfmove_clock(), position.fullmove_number())
+                                .with_castling_rooks(position.castling_rooks()),
+                        ),
+                    )*
+                    $(
+                        ($ep_white, true, $ep_wk, $ep_wq, $ep_bk, $ep_bq) => AnyBoard::$ep_variant(
+                            Board::new(*position.pieces(), position.en_passant_square())
+                                .with_clocks(position.halfmove_clock(), position.fullmove_number())
+                                .with_castling_rooks(position.castling_rooks()),
+                        ),
+                    )*
+                }
+            }
+        }
+
+        macro_rules! dispatch {
+            ($any:expr, $board:ident => $body:expr) => {
+                match $any {
+                    $(AnyBoard::$variant($board) => $body,)*
+                    $(AnyBoard::$ep_variant($board) => $body,)*
+                }
+            };
+        }
+
+        /// `dispatch!` over the boards where a pawn may be captured en passant, `None` for the rest
+        macro_rules! dispatch_en_passant {
+            ($any:expr, $board:ident => $body:expr) => {
+                match $any {
+                    $(AnyBoard::$ep_variant($board) => Some($body),)*
+                    _ => None,
+                }
+            };
+        }
+    };
+}
+
+any_board! {
+    WhiteKQkq => <true, false, true, true, true, true>,
+    WhiteKQk => <true, false, true, true, true, false>,
+    WhiteKQq => <true, false, true, true, false, true>,
+    WhiteKQ => <true, false, true, true, false, false>,
+    WhiteKkq => <true, false, true, false, true, true>,
+    WhiteKk => <true, false, true, false, true, false>,
+    WhiteKq => <true, false, true, false, false, true>,
+    WhiteK => <true, false, true, false, false, false>,
+    WhiteQkq => <true, false, false, true, true, true>,
+    WhiteQk => <true, false, false, true, true, false>,
+    WhiteQq => <true, false, false, true, false, true>,
+    WhiteQ => <true, false, false, true, false, false>,
+    Whitekq => <true, false, false, false, true, true>,
+    Whitek => <true, false, false, false, true, false>,
+    Whiteq => <true, false, false, false, false, true>,
+    White => <true, false, false, false, false, false>,
+    BlackKQkq => <false, false, true, true, true, true>,
+    BlackKQk => <false, false, true, true, true, false>,
+    BlackKQq => <false, false, true, true, false, true>,
+    BlackKQ => <false, false, true, true, false, false>,
+    BlackKkq => <false, false, true, false, true, true>,
+    BlackKk => <false, false, true, false, true, false>,
+    BlackKq => <false, false, true, false, false, true>,
+    BlackK => <false, false, true, false, false, false>,
+    BlackQkq => <false, false, false, true, true, true>,
+    BlackQk => <false, false, false, true, true, false>,
+    BlackQq => <false, false, false, true, false, true>,
+    BlackQ => <false, false, false, true, false, false>,
+    Blackkq => <false, false, false, false, true, true>,
+    Blackk => <false, false, false, false, true, false>,
+    Blackq => <false, false, false, false, false, true>,
+    Black => <false, false, false, false, false, false>,
+    ;
+    WhiteEpKQkq => <true, true, true, true, true, true>,
+    WhiteEpKQk => <true, true, true, true, true, false>,
+    WhiteEpKQq => <true, true, true, true, false, true>,
+    WhiteEpKQ => <true, true, true, true, false, false>,
+    WhiteEpKkq => <true, true, true, false, true, true>,
+    WhiteEpKk => <true, true, true, false, true, false>,
+    WhiteEpKq => <true, true, true, false, false, true>,
+    WhiteEpK => <true, true, true, false, false, false>,
+    WhiteEpQkq => <true, true, false, true, true, true>,
+    WhiteEpQk => <true, true, false, true, true, false>,
+    WhiteEpQq => <true, true, false, true, false, true>,
+    WhiteEpQ => <true, true, false, true, false, false>,
+    WhiteEpkq => <true, true, false, false, true, true>,
+    WhiteEpk => <true, true, false, false, true, false>,
+    WhiteEpq => <true, true, false, false, false, true>,
+    WhiteEp => <true, true, false, false, false, false>,
+    BlackEpKQkq => <false, true, true, true, true, true>,
+    BlackEpKQk => <false, true, true, true, true, false>,
+    BlackEpKQq => <false, true, true, true, false, true>,
+    BlackEpKQ => <false, true, true, true, false, false>,
+    BlackEpKkq => <false, true, true, false, true, true>,
+    BlackEpKk => <false, true, true, false, true, false>,
+    BlackEpKq => <false, true, true, false, false, true>,
+    BlackEpK => <false, true, true, false, false, false>,
+    BlackEpQkq => <false, true, false, true, true, true>,
+    BlackEpQk => <false, true, false, true, true, false>,
+    BlackEpQq => <false, true, false, true, false, true>,
+    BlackEpQ => <false, true, false, true, false, false>,
+    BlackEpkq => <false, true, false, false, true, true>,
+    BlackEpk => <false, true, false, false, true, false>,
+    BlackEpq => <false, true, false, false, false, true>,
+    BlackEp => <false, true, false, false, false, false>,
+}
+
+impl AnyBoard {
+    pub const fn pieces(&self) -> &PieceArrangement {
+        dispatch!(self, board => board.pieces())
+    }
+
+    pub const fn is_white_to_move(&self) -> bool {
+        dispatch!(self, board => board.is_white_to_move())
+    }
+
+    pub const fn side_to_move(&self) -> Player {
+        if self.is_white_to_move() {
+            Player::White
+        } else {
+            Player::Black
+        }
+    }
+
+    pub const fn en_passant_square(&self) -> Option<Square> {
+        dispatch!(self, board => board.en_passant_square())
+    }
+
+    pub const fn halfmove_clock(&self) -> u16 {
+        dispatch!(self, board => board.halfmove_clock())
+    }
+
+    pub const fn fullmove_number(&self) -> u16 {
+        dispatch!(self, board => board.fullmove_number())
+    }
+
+    pub const fn has_castle_rights(&self, player: Player, direction: CastleDirection) -> bool {
+        dispatch!(self, board => board.has_castle_rights(player, direction))
+    }
+
+    /// See [`Board::zobrist`]
+    pub const fn zobrist(&self) -> u64 {
+        dispatch!(self, board => board.zobrist())
+    }
+
+    /// See [`Board::with_network`]
+    pub fn with_network(self, network: Option<&'static Network>) -> AnyBoard {
+        dispatch!(self, board => board.with_network(network).into())
+    }
+
+    pub fn fen(&self) -> String {
+        dispatch!(*self, board => board.fen())
+    }
+
+    /// See [`Board::shredder_fen`]
+    pub fn shredder_fen(&self) -> String {
+        dispatch!(*self, board => board.shredder_fen())
+    }
+
+    /// See [`CastlingRooks`]
+    pub const fn castling_rooks(&self) -> CastlingRooks {
+        dispatch!(self, board => board.castling_rooks())
+    }
+
+    /// See [`Board::can_castle`]
+    pub const fn can_castle(&self, direction: CastleDirection) -> bool {
+        dispatch!(*self, board => match direction {
+            CastleDirection::KingSide => board.can_castle::<{ CastleDirection::KingSide }>(),
+            CastleDirection::QueenSide => board.can_castle::<{ CastleDirection::QueenSide }>(),
+        })
+    }
+
+    /// See [`Board::checkers`]
+    pub const fn checkers(&self) -> Bitboard {
+        dispatch!(self, board => board.checkers())
+    }
+
+    pub const fn in_check(&self) -> bool {
+        dispatch!(self, board => board.in_check())
+    }
+
+    /// See [`Board::pinned`]
+    pub fn pinned(&self) -> Bitboard {
+        dispatch!(self, board => board.pinned())
+    }
+
+    /// See [`Board::termination`]
+    pub fn termination(&self) -> Option<Termination> {
+        dispatch!(self, board => board.termination())
+    }
+
+    pub fn legal_moves(&self) -> MoveList {
+        dispatch!(self, board => board.legal_moves())
+    }
+
+    /// Play `mv` if it is legal, for moves from untrusted input
+    ///
+    /// Only the squares and promotion piece of `mv` are taken from the caller. Its kind, whether
+    /// quiet, a capture, castling, en passant or a promotion, comes from the matching legal move,
+    /// and [`AnyBoard::make_move`] then picks the transition for it and drops the castling rights
+    /// it costs. Castling may be written as the king taking its own rook, which is the only way
+    /// to castle in a Chess960 position where a king step lands on the same square.
+    pub fn apply_move(self, mv: Move) -> Result<AnyBoard, IllegalMove> {
+        let (moves, uci) = (self.legal_moves(), mv.to_uci());
+        let legal = moves
+            .find_uci_chess960(uci, self.castling_rooks())
+            .or_else(|| moves.find_uci(uci))
+            .ok_or(IllegalMove(mv))?;
+        Ok(self.make_move(legal))
+    }
+
+    /// Play `mv`, which must be one of [`AnyBoard::legal_moves`]
+    pub fn make_move(self, mv: Move) -> AnyBoard {
+        let (from, to) = (mv.from(), mv.to());
+        let is_king = self.pieces().piece_type_on(from) == Some(PieceType::King);
+        let moved: AnyBoard = match mv.kind() {
+            // Legal move generation only offers en passant on a board with a pawn to take
+            MoveKind::EnPassant => {
+                dispatch_en_passant!(self, board => board.en_passant_capture(from, to).into())
+                    .expect("en passant without an en passant square")
+            }
+            kind => dispatch!(self, board => match kind {
+                MoveKind::EnPassant => unreachable!(),
+                MoveKind::Quiet if is_king => board.king_move(from, to).into(),
+                MoveKind::Quiet => board.quiet_move(from, to).into(),
+                MoveKind::DoublePawnPush => board.double_pawn_push(from, to).into(),
+                MoveKind::Castle(CastleDirection::KingSide) => {
+                    board.castle::<{ CastleDirection::KingSide }>().into()
+                }
+                MoveKind::Castle(CastleDirection::QueenSide) => {
+                    board.castle::<{ CastleDirection::QueenSide }>().into()
+                }
+                MoveKind::Capture if is_king => board.king_capture(from, to).into(),
+                MoveKind::Capture => board.capture(from, to).into(),
+                MoveKind::Promotion(PromotionPieceType::Knight) => {
+                    board.promote::<{ NonKingPieceType::Knight }>(from, to).into()
+                }
+                MoveKind::Promotion(PromotionPieceType::Bishop) => {
+                    board.promote::<{ NonKingPieceType::Bishop }>(from, to).into()
+                }
+                MoveKind::Promotion(PromotionPieceType::Rook) => {
+                    board.promote::<{ NonKingPieceType::Rook }>(from, to).into()
+                }
+                MoveKind::Promotion(PromotionPieceType::Queen) => {
+                    board.promote::<{ NonKingPieceType::Queen }>(from, to).into()
+                }
+                MoveKind::PromotionCapture(PromotionPieceType::Knight) => {
+                    board.promote_capture::<{ NonKingPieceType::Knight }>(from, to).into()
+                }
+                MoveKind::PromotionCapture(PromotionPieceType::Bishop) => {
+                    board.promote_capture::<{ NonKingPieceType::Bishop }>(from, to).into()
+                }
+                MoveKind::PromotionCapture(PromotionPieceType::Rook) => {
+                    board.promote_capture::<{ NonKingPieceType::Rook }>(from, to).into()
+                }
+                MoveKind::PromotionCapture(PromotionPieceType::Queen) => {
+                    board.promote_capture::<{ NonKingPieceType::Queen }>(from, to).into()
+                }
+            }),
+        };
+
+        // A castling rook moving off or captured on its square takes that castling right with it
+        let rooks = self.castling_rooks();
+        [Player::White, Player::Black]
+            .into_iter()
+            .flat_map(|player| {
+                [CastleDirection::KingSide, CastleDirection::QueenSide]
+                    .map(|direction| (player, direction, rooks.square(player, direction)))
+            })
+            .filter(|&(_, _, rook)| from == rook || to == rook)
+            .fold(moved, |board, (player, direction, _)| {
+                if board.has_castle_rights(player, direction) {
+                    board.remove_castle_rights(player, direction)
+                } else {
+                    board
+                }
+            })
+    }
+
+    /// See [`Board::remove_castle_rights`]
+    pub fn remove_castle_rights(self, player: Player, direction: CastleDirection) -> AnyBoard {
+        dispatch!(self, board => match (player, direction) {
+            (Player::White, CastleDirection::KingSide) => board
+                .remove_castle_rights::<{ Player::White }, { CastleDirection::KingSide }>()
+                .into(),
+            (Player::White, CastleDirection::QueenSide) => board
+                .remove_castle_rights::<{ Player::White }, { CastleDirection::QueenSide }>()
+                .into(),
+            (Player::Black, CastleDirection::KingSide) => board
+                .remove_castle_rights::<{ Player::Black }, { CastleDirection::KingSide }>()
+                .into(),
+            (Player::Black, CastleDirection::QueenSide) => board
+                .remove_castle_rights::<{ Player::Black }, { CastleDirection::QueenSide }>()
+                .into(),
+        })
+    }
+}
+
+impl Default for AnyBoard {
+    fn default() -> Self {
+        DEFAULT_BOARD.into()
+    }
+}
+
+impl FromStr for AnyBoard {
+    type Err = ParseBoardError;
+
+    /// Parse `fen` and check it with [`PositionBuilder::build`], as move generation trusts the
+    /// board it is given
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        let position = fen.parse::<Position>()?;
+        Ok(PositionBuilder::from(position).build()?.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::any_board::{AnyBoard, IllegalMove, ParseBoardError};
+    use crate::board::DEFAULT_BOARD;
+    use crate::castle_direction::CastleDirection;
+    use crate::chess_move::{Move, MoveKind};
+    use crate::fen::{FenError, FenField, STARTING_FEN};
+    use crate::player::Player;
+    use crate::position_builder::PositionError;
+    use crate::square::Square::*;
+    use test_case::test_case;
+
+    const CASTLING: &str = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+
+    fn play(fen: &str, moves: &[&str]) -> AnyBoard {
+        moves
+            .iter()
+            .fold(fen.parse().unwrap(), |board: AnyBoard, uci| {
+                let mv = board.legal_moves().find_uci(uci.parse().unwrap());
+                board.make_move(
+                    mv.unwrap_or_else(|| panic!("{uci} is not legal in {}", board.fen())),
+                )
+            })
+    }
+
+    #[test]
+    fn default_is_the_starting_position() {
+        assert_eq!(AnyBoard::default(), AnyBoard::WhiteKQkq(DEFAULT_BOARD));
+        assert_eq!(STARTING_FEN.parse::<AnyBoard>(), Ok(AnyBoard::default()));
+    }
+
+    #[test_case("P3k3/8/8/8/8/8/8/4K3 w - - 0 1", ParseBoardError::Position(PositionError::PawnOnBackRank(A8)); "pawn on the back rank")]
+    #[test_case("4k3/4R3/8/8/8/8/8/4K3 w - - 0 1", ParseBoardError::Position(PositionError::OpponentInCheck); "opponent in check")]
+    #[test_case("4k3/8/8/3P4/8/8/8/4K3 w - e6 0 1", ParseBoardError::Fen(FenError { field: FenField::EnPassant, offset: 26 }); "en passant without a pawn")]
+    fn rejects_positions_move_generation_cannot_handle(fen: &str, error: ParseBoardError) {
+        assert_eq!(fen.parse::<AnyBoard>(), Err(error));
+    }
+
+    #[test_case("rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 1"; "white en passant")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b Kq - 0 1"; "black partial rights")]
+    #[test_case("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1"; "no rights")]
+    #[test_case("4k3/8/8/8/8/8/8/4K2R w K - 37 81"; "clocks")]
+    fn fen_round_trips(fen: &str) {
+        assert_eq!(fen.parse::<AnyBoard>().unwrap().fen(), fen);
+    }
+
+    #[test]
+    fn picks_the_variant_from_the_fen() {
+        let board: AnyBoard = "4k2r/8/8/8/3pP3/8/8/R3K3 b Qk e3 0 1".parse().unwrap();
+        assert!(matches!(board, AnyBoard::BlackEpQk(_)));
+        assert!(!board.is_white_to_move());
+        assert!(board.has_castle_rights(Player::White, CastleDirection::QueenSide));
+        assert!(!board.has_castle_rights(Player::White, CastleDirection::KingSide));
+    }
+
+    #[test]
+    fn plays_moves_of_every_kind() {
+        let board = play(
+            STARTING_FEN,
+            &[
+                "e2e4", "d7d5", "e4d5", "c7c5", "d5c6", "g8f6", "c6b7", "e7e6", "b7a8q",
+            ],
+        );
+        assert_eq!(
+            board.fen(),
+            "Qnbqkb1r/p4ppp/4pn2/8/8/8/PPPP1PPP/RNBQKBNR b KQk - 0 5"
+        );
+
+        let board = play("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", &["e1g1", "e8c8"]);
+        assert_eq!(board.fen(), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2");
+    }
+
+    #[test_case(&["a1a2"], "r3k2r/8/8/8/8/8/R7/4K2R b Kkq - 1 1"; "rook leaves its corner")]
+    #[test_case(&["e1e2"], "r3k2r/8/8/8/8/8/4K3/R6R b kq - 1 1"; "king move")]
+    #[test_case(&["a1a8"], "R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1"; "rook captures rook in the corner")]
+    #[test_case(&["h1h7", "h8h7"], "r3k3/7r/8/8/8/8/8/R3K3 w Qq - 0 2"; "rook captured off its corner")]
+    #[test_case(&["e1d1", "a8a1"], "4k2r/8/8/8/8/8/8/r2K3R w k - 0 2"; "rook captured in its corner")]
+    fn castling_rights_follow_the_rooks(moves: &[&str], expected: &str) {
+        let board = play("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", moves);
+        assert_eq!(board.fen(), expected);
+    }
+
+    #[test]
+    fn promotion_capture_in_the_corner_removes_rights() {
+        let board = play("r3k3/1P6/8/8/8/8/8/4K3 w q - 0 1", &["b7a8n"]);
+        assert_eq!(board.fen(), "N3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn chess960_castling_follows_the_castling_rooks() {
+        const FEN: &str = "1r2k2r/8/8/8/8/8/8/1R2K1R1 w GBhb - 0 1";
+        let board: AnyBoard = FEN.parse().unwrap();
+        assert_eq!(board.shredder_fen(), FEN);
+        assert_eq!(board.fen(), "1r2k2r/8/8/8/8/8/8/1R2K1R1 w KQkq - 0 1");
+
+        let board = play(FEN, &["e1g1", "e8c8"]);
+        assert_eq!(board.fen(), "2kr3r/8/8/8/8/8/8/1R3RK1 w - - 2 2");
+        let board = play(FEN, &["b1b2", "h8g8"]);
+        assert_eq!(
+            board.shredder_fen(),
+            "1r2k1r1/8/8/8/8/8/1R6/4K1R1 w Gb - 2 2"
+        );
+    }
+
+    #[test_case(STARTING_FEN, Move::new(E2, E4, MoveKind::Quiet), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"; "double push")]
+    #[test_case(CASTLING, Move::new(E1, G1, MoveKind::Quiet), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1"; "castle")]
+    #[test_case(CASTLING, Move::new(E1, H1, MoveKind::Quiet), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1"; "castle as the king taking its rook")]
+    #[test_case(CASTLING, Move::new(H1, H8, MoveKind::Quiet), "r3k2R/8/8/8/8/8/8/R3K3 b Qq - 0 1"; "rook capture drops both sides' rights")]
+    #[test_case("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1", Move::new(D5, E6, MoveKind::Quiet), "4k3/8/4P3/8/8/8/8/4K3 b - - 0 1"; "en passant")]
+    #[test_case("rk6/8/8/8/8/8/8/RK6 w Aa - 0 1", Move::new(B1, A1, MoveKind::Quiet), "rk6/8/8/8/8/8/8/2KR4 b q - 1 1"; "chess960 castle onto the next square")]
+    #[test_case("rk6/8/8/8/8/8/8/RK6 w Aa - 0 1", Move::new(B1, C1, MoveKind::Castle(CastleDirection::QueenSide)), "rk6/8/8/8/8/8/8/R1K5 b q - 1 1"; "chess960 king step to the same square")]
+    fn apply_move_plays_legal_moves(fen: &str, mv: Move, expected: &str) {
+        let board: AnyBoard = fen.parse().unwrap();
+        assert_eq!(
+            board.apply_move(mv).map(|board| board.fen()),
+            Ok(expected.to_string())
+        );
+    }
+
+    #[test_case(STARTING_FEN, Move::new(E2, E5, MoveKind::Quiet); "teleport")]
+    #[test_case(STARTING_FEN, Move::new(E7, E5, MoveKind::DoublePawnPush); "the other side's pawn")]
+    #[test_case("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1", Move::new(E2, C3, MoveKind::Quiet); "pinned knight")]
+    #[test_case("4k3/8/8/8/8/8/8/R3K2r w Q - 0 1", Move::new(E1, C1, MoveKind::Castle(CastleDirection::QueenSide)); "castle out of check")]
+    fn apply_move_refuses_illegal_moves(fen: &str, mv: Move) {
+        let board: AnyBoard = fen.parse().unwrap();
+        assert_eq!(board.apply_move(mv), Err(IllegalMove(mv)));
+    }
+
+    #[test]
+    fn can_castle_dispatches_on_direction() {
+        let board: AnyBoard = "r3k2r/8/8/8/8/8/8/R3K1R1 w Qkq - 0 1".parse().unwrap();
+        assert!(board.can_castle(CastleDirection::QueenSide));
+        assert!(!board.can_castle(CastleDirection::KingSide));
+    }
+}
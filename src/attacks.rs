@@ -0,0 +1,295 @@
+use crate::bitboard::{Bitboard, EMPTY_BITBOARD};
+use crate::magic::{bishop_magic_attacks, rook_magic_attacks};
+use crate::square::{Square, SQUARES};
+
+const NOT_A_FILE: Bitboard = 0xFEFE_FEFE_FEFE_FEFE;
+const NOT_H_FILE: Bitboard = 0x7F7F_7F7F_7F7F_7F7F;
+const NOT_AB_FILES: Bitboard = 0xFCFC_FCFC_FCFC_FCFC;
+const NOT_GH_FILES: Bitboard = 0x3F3F_3F3F_3F3F_3F3F;
+
+/// File and rank steps for the four orthogonal rays
+pub(crate) const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+/// File and rank steps for the four diagonal rays
+pub(crate) const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+/// Squares attacked by every pawn in `pawns` for the given side
+pub const fn pawn_attacks_mask<const IS_WHITE: bool>(pawns: Bitboard) -> Bitboard {
+    if IS_WHITE {
+        ((pawns & NOT_A_FILE) << 7) | ((pawns & NOT_H_FILE) << 9)
+    } else {
+        ((pawns & NOT_A_FILE) >> 9) | ((pawns & NOT_H_FILE) >> 7)
+    }
+}
+
+/// Squares attacked by every knight in `knights`
+pub const fn knight_attacks_mask(knights: Bitboard) -> Bitboard {
+    ((knights & NOT_H_FILE) << 17)
+        | ((knights & NOT_A_FILE) << 15)
+        | ((knights & NOT_GH_FILES) << 10)
+        | ((knights & NOT_AB_FILES) << 6)
+        | ((knights & NOT_A_FILE) >> 17)
+        | ((knights & NOT_H_FILE) >> 15)
+        | ((knights & NOT_AB_FILES) >> 10)
+        | ((knights & NOT_GH_FILES) >> 6)
+}
+
+/// Squares attacked by every king in `kings`
+pub const fn king_attacks_mask(kings: Bitboard) -> Bitboard {
+    let sideways = ((kings & NOT_A_FILE) >> 1) | ((kings & NOT_H_FILE) << 1);
+    let row = kings | sideways;
+    sideways | (row << 8) | (row >> 8)
+}
+
+const KNIGHT_ATTACKS: [Bitboard; Square::COUNT] = {
+    let mut table = [EMPTY_BITBOARD; Square::COUNT];
+    let mut square = 0;
+    while square < Square::COUNT {
+        table[square] = knight_attacks_mask(1u64 << square);
+        square += 1;
+    }
+
+    table
+};
+
+const KING_ATTACKS: [Bitboard; Square::COUNT] = {
+    let mut table = [EMPTY_BITBOARD; Square::COUNT];
+    let mut square = 0;
+    while square < Square::COUNT {
+        table[square] = king_attacks_mask(1u64 << square);
+        square += 1;
+    }
+
+    table
+};
+
+const PAWN_ATTACKS: [[Bitboard; Square::COUNT]; 2] = {
+    let mut table = [[EMPTY_BITBOARD; Square::COUNT]; 2];
+    let mut square = 0;
+    while square < Square::COUNT {
+        table[false as usize][square] = pawn_attacks_mask::<false>(1u64 << square);
+        table[true as usize][square] = pawn_attacks_mask::<true>(1u64 << square);
+        square += 1;
+    }
+
+    table
+};
+
+/// Squares a pawn of the given side on `square` attacks
+pub const fn pawn_attacks<const IS_WHITE: bool>(square: Square) -> Bitboard {
+    PAWN_ATTACKS[IS_WHITE as usize][square as usize]
+}
+
+/// Squares a knight on `square` attacks
+pub const fn knight_attacks(square: Square) -> Bitboard {
+    KNIGHT_ATTACKS[square as usize]
+}
+
+/// Squares a king on `square` attacks
+pub const fn king_attacks(square: Square) -> Bitboard {
+    KING_ATTACKS[square as usize]
+}
+
+/// Walk each ray from `square` until it leaves the board or hits an occupied square (inclusive)
+///
+/// Too slow for search, this is used to build the magic attack tables
+pub(crate) const fn ray_attacks(
+    square: Square,
+    occupied: Bitboard,
+    directions: &[(i8, i8); 4],
+) -> Bitboard {
+    let mut attacks = EMPTY_BITBOARD;
+    let mut direction = 0;
+    while direction < directions.len() {
+        let (file_step, rank_step) = directions[direction];
+        let mut file = (square as u8 % 8) as i8 + file_step;
+        let mut rank = (square as u8 / 8) as i8 + rank_step;
+        while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+            let bit = 1u64 << (rank * 8 + file);
+            attacks |= bit;
+            if occupied & bit != EMPTY_BITBOARD {
+                break;
+            }
+            file += file_step;
+            rank += rank_step;
+        }
+        direction += 1;
+    }
+
+    attacks
+}
+
+/// Squares a bishop on `square` attacks given the `occupied` blockers
+pub const fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    bishop_magic_attacks(square, occupied)
+}
+
+/// Squares a rook on `square` attacks given the `occupied` blockers
+pub const fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    rook_magic_attacks(square, occupied)
+}
+
+/// Squares a queen on `square` attacks given the `occupied` blockers
+pub const fn queen_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    bishop_attacks(square, occupied) | rook_attacks(square, occupied)
+}
+
+/// Squares strictly between every pair of squares sharing a rank, file or diagonal
+static BETWEEN: [[Bitboard; Square::COUNT]; Square::COUNT] = {
+    let mut table = [[EMPTY_BITBOARD; Square::COUNT]; Square::COUNT];
+    let mut a = 0;
+    while a < Square::COUNT {
+        let mut b = 0;
+        while b < Square::COUNT {
+            let (square_a, square_b) = (SQUARES[a], SQUARES[b]);
+            let (bit_a, bit_b) = (square_a.to_bit(), square_b.to_bit());
+            if ray_attacks(square_a, EMPTY_BITBOARD, &ROOK_DIRECTIONS) & bit_b != EMPTY_BITBOARD {
+                table[a][b] = ray_attacks(square_a, bit_b, &ROOK_DIRECTIONS)
+                    & ray_attacks(square_b, bit_a, &ROOK_DIRECTIONS);
+            } else if ray_attacks(square_a, EMPTY_BITBOARD, &BISHOP_DIRECTIONS) & bit_b
+                != EMPTY_BITBOARD
+            {
+                table[a][b] = ray_attacks(square_a, bit_b, &BISHOP_DIRECTIONS)
+                    & ray_attacks(square_b, bit_a, &BISHOP_DIRECTIONS);
+            }
+            b += 1;
+        }
+        a += 1;
+    }
+
+    table
+};
+
+/// The full edge to edge line through every pair of squares sharing a rank, file or diagonal
+static LINE: [[Bitboard; Square::COUNT]; Square::COUNT] = {
+    let mut table = [[EMPTY_BITBOARD; Square::COUNT]; Square::COUNT];
+    let mut a = 0;
+    while a < Square::COUNT {
+        let mut b = 0;
+        while b < Square::COUNT {
+            let (square_a, square_b) = (SQUARES[a], SQUARES[b]);
+            let (bit_a, bit_b) = (square_a.to_bit(), square_b.to_bit());
+            let rook_a = ray_attacks(square_a, EMPTY_BITBOARD, &ROOK_DIRECTIONS);
+            let bishop_a = ray_attacks(square_a, EMPTY_BITBOARD, &BISHOP_DIRECTIONS);
+            if rook_a & bit_b != EMPTY_BITBOARD {
+                table[a][b] = (rook_a & ray_attacks(square_b, EMPTY_BITBOARD, &ROOK_DIRECTIONS))
+                    | bit_a
+                    | bit_b;
+            } else if bishop_a & bit_b != EMPTY_BITBOARD {
+                table[a][b] = (bishop_a
+                    & ray_attacks(square_b, EMPTY_BITBOARD, &BISHOP_DIRECTIONS))
+                    | bit_a
+                    | bit_b;
+            }
+            b += 1;
+        }
+        a += 1;
+    }
+
+    table
+};
+
+/// Squares strictly between `a` and `b`, empty unless they share a rank, file or diagonal
+pub const fn between(a: Square, b: Square) -> Bitboard {
+    BETWEEN[a as usize][b as usize]
+}
+
+/// The whole line through `a` and `b`, empty unless they share a rank, file or diagonal
+pub const fn line(a: Square, b: Square) -> Bitboard {
+    LINE[a as usize][b as usize]
+}
+
+#[cfg(test)]
+mod test {
+    use crate::attacks::{
+        between, bishop_attacks, king_attacks, knight_attacks, line, pawn_attacks, queen_attacks,
+        rook_attacks,
+    };
+    use crate::bitboard::{Bitboard, EMPTY_BITBOARD};
+    use crate::square::Square::{self, *};
+    use test_case::test_case;
+
+    #[test_case(A1, 0x0002_0400; "corner a1")]
+    #[test_case(H8, 0x0020_4000_0000_0000; "corner h8")]
+    #[test_case(B1, 0x0005_0800; "edge b1")]
+    #[test_case(D4, 0x0000_1422_0022_1400; "center d4")]
+    #[test_case(G6, 0xA010_0010_A000_0000; "near h file g6")]
+    fn knight_attack_scenarios(square: Square, expected: Bitboard) {
+        assert_eq!(knight_attacks(square), expected, "{square:?}");
+    }
+
+    #[test_case(A1, 0x0302; "corner a1")]
+    #[test_case(H8, 0x40C0_0000_0000_0000; "corner h8")]
+    #[test_case(E1, 0x3828; "edge e1")]
+    #[test_case(D4, 0x0000_001C_141C_0000; "center d4")]
+    fn king_attack_scenarios(square: Square, expected: Bitboard) {
+        assert_eq!(king_attacks(square), expected, "{square:?}");
+    }
+
+    #[test_case(true, E2, 0x0028_0000; "white e2")]
+    #[test_case(true, A2, 0x0002_0000; "white a2 edge")]
+    #[test_case(true, H7, 0x4000_0000_0000_0000; "white h7 edge")]
+    #[test_case(false, E7, 0x0000_2800_0000_0000; "black e7")]
+    #[test_case(false, H7, 0x0000_4000_0000_0000; "black h7 edge")]
+    #[test_case(false, A2, 0x02; "black a2 edge")]
+    fn pawn_attack_scenarios(is_white: bool, square: Square, expected: Bitboard) {
+        let actual = if is_white {
+            pawn_attacks::<true>(square)
+        } else {
+            pawn_attacks::<false>(square)
+        };
+        assert_eq!(actual, expected, "{square:?}");
+    }
+
+    #[test_case(A1, EMPTY_BITBOARD, 0x0101_0101_0101_01FE; "a1 empty board")]
+    #[test_case(D4, EMPTY_BITBOARD, 0x0808_0808_F708_0808; "d4 empty board")]
+    #[test_case(A1, 0x0100 | 0x02, 0x0102; "a1 boxed in")]
+    #[test_case(D4, 0x0008_0000_2200_0800, 0x0008_0808_3608_0800; "d4 with blockers")]
+    fn rook_attack_scenarios(square: Square, occupied: Bitboard, expected: Bitboard) {
+        assert_eq!(
+            rook_attacks(square, occupied),
+            expected,
+            "{square:?} {occupied:X}"
+        );
+    }
+
+    #[test_case(A1, EMPTY_BITBOARD, 0x8040_2010_0804_0200; "a1 empty board")]
+    #[test_case(D4, EMPTY_BITBOARD, 0x8041_2214_0014_2241; "d4 empty board")]
+    #[test_case(C1, 0x0A00, 0x0A00; "c1 blocked by pawns")]
+    #[test_case(D4, 0x0000_0200_0000_0040, 0x8040_2214_0014_2241; "d4 with blockers")]
+    fn bishop_attack_scenarios(square: Square, occupied: Bitboard, expected: Bitboard) {
+        assert_eq!(
+            bishop_attacks(square, occupied),
+            expected,
+            "{square:?} {occupied:X}"
+        );
+    }
+
+    #[test_case(A1, H8, 0x0040_2010_0804_0200; "long diagonal")]
+    #[test_case(E1, E8, 0x0010_1010_1010_1000; "e file")]
+    #[test_case(B3, G3, 0x3C_0000; "third rank")]
+    #[test_case(D4, E5, EMPTY_BITBOARD; "adjacent")]
+    #[test_case(A1, B3, EMPTY_BITBOARD; "unaligned")]
+    fn between_scenarios(a: Square, b: Square, expected: Bitboard) {
+        assert_eq!(between(a, b), expected, "{a:?} {b:?}");
+        assert_eq!(between(b, a), expected, "{b:?} {a:?}");
+    }
+
+    #[test_case(D4, E5, 0x8040_2010_0804_0201; "long diagonal")]
+    #[test_case(E2, E4, 0x1010_1010_1010_1010; "e file")]
+    #[test_case(A1, B3, EMPTY_BITBOARD; "unaligned")]
+    fn line_scenarios(a: Square, b: Square, expected: Bitboard) {
+        assert_eq!(line(a, b), expected, "{a:?} {b:?}");
+    }
+
+    #[test]
+    fn queen_attacks_are_union_of_rook_and_bishop() {
+        let occupied = 0x0042_0010_2400_8100;
+        for square in crate::square::SQUARES {
+            assert_eq!(
+                queen_attacks(square, occupied),
+                rook_attacks(square, occupied) | bishop_attacks(square, occupied),
+                "{square:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Tune the evaluation weights to a file of labelled positions
+//!
+//! `tune <dataset> [output.rs] [passes]` reads one FEN and game result per line, fits the
+//! evaluation to the results and writes the tuned `WEIGHTS` constant to `output.rs`, by default
+//! `weights.rs`, after every pass.
+
+use magnesium::evaluation::WEIGHTS;
+use magnesium::tuning::{loss, optimal_k, parse_dataset, to_rust_source, tune};
+
+use std::process::ExitCode;
+use std::{env, fs};
+
+const DEFAULT_OUTPUT: &str = "weights.rs";
+const DEFAULT_PASSES: usize = 100;
+
+pub fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let Some(dataset) = args.next() else {
+        eprintln!("usage: tune <dataset> [output.rs] [passes]");
+        return ExitCode::FAILURE;
+    };
+    let output = args.next().unwrap_or_else(|| DEFAULT_OUTPUT.to_string());
+    let passes = match args.next().map(|passes| passes.parse()) {
+        None => DEFAULT_PASSES,
+        Some(Ok(passes)) => passes,
+        Some(Err(error)) => {
+            eprintln!("invalid number of passes: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let positions = match fs::read_to_string(&dataset)
+        .map_err(|error| error.to_string())
+        .and_then(|text| parse_dataset(&text).map_err(|error| error.to_string()))
+    {
+        Ok(positions) => positions,
+        Err(error) => {
+            eprintln!("{dataset}: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    eprintln!("loaded {} positions", positions.len());
+
+    let k = optimal_k(&positions, &WEIGHTS);
+    eprintln!("k = {k:.4}, loss = {:.6}", loss(&positions, &WEIGHTS, k));
+
+    let mut saved = Ok(());
+    tune(&positions, &WEIGHTS, k, passes, |pass, loss, weights| {
+        eprintln!("pass {pass}: loss = {loss:.6}");
+        if saved.is_ok() {
+            saved = fs::write(&output, to_rust_source(weights));
+        }
+    });
+    if let Err(error) = saved {
+        eprintln!("{output}: {error}");
+        return ExitCode::FAILURE;
+    }
+    eprintln!("wrote {output}");
+
+    ExitCode::SUCCESS
+}
@@ -1,6 +1,21 @@
+use crate::square::{Square, SQUARES};
+
 /// A board mask of toggle-able squares
 pub type Bitboard = u64;
 /// A bitboard with only a single bit set
 pub type Bit = u64;
 
 pub const EMPTY_BITBOARD: Bitboard = 0u64;
+
+/// Iterate the squares of every set bit, from A1 towards H8
+pub fn squares(mut bitboard: Bitboard) -> impl Iterator<Item = Square> {
+    core::iter::from_fn(move || {
+        if bitboard == EMPTY_BITBOARD {
+            None
+        } else {
+            let square = SQUARES[bitboard.trailing_zeros() as usize];
+            bitboard &= bitboard - 1;
+            Some(square)
+        }
+    })
+}
@@ -1,9 +1,13 @@
+use crate::attacks::{between, pawn_attacks};
 use crate::bitboard::{Bitboard, EMPTY_BITBOARD};
-use crate::castle_direction::CastleDirection;
-use crate::piece_arrangement::PieceArrangement;
+use crate::castle_direction::{CastleDirection, CastlingRooks};
+use crate::fen::{push_castling, push_placement};
+use crate::nnue::Network;
+use crate::piece_arrangement::{ArrangementError, PieceArrangement};
 use crate::piece_type::{NonKingPieceType, PieceType};
 use crate::player::Player;
 use crate::square::{Square, Square::*, SQUARES};
+use crate::zobrist::{en_passant_key, state_key};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Board<
@@ -15,6 +19,14 @@ pub struct Board<
     const BLACK_HAS_QUEEN_CASTLE_RIGHTS: bool,
 > {
     pieces: PieceArrangement,
+    /// The square a pawn that just double pushed skipped over, present exactly when `HAS_EP_PAWN`
+    en_passant: Option<Square>,
+    /// Halfmoves since the last capture or pawn move, for the fifty-move rule
+    halfmove_clock: u16,
+    /// Starts at 1 and increments after every black move
+    fullmove_number: u16,
+    /// The rooks the castling rights refer to, only ever off the corners in Chess960
+    castling_rooks: CastlingRooks,
 }
 
 pub const DEFAULT_BOARD: Board<true, false, true, true, true, true> = Board {
@@ -53,6 +65,10 @@ pub const DEFAULT_BOARD: Board<true, false, true, true, true, true> = Board {
         .add_piece::<false, { NonKingPieceType::Pawn }>(F7)
         .add_piece::<false, { NonKingPieceType::Pawn }>(G7)
         .add_piece::<false, { NonKingPieceType::Pawn }>(H7),
+    en_passant: None,
+    halfmove_clock: 0,
+    fullmove_number: 1,
+    castling_rooks: CastlingRooks::STANDARD,
 };
 
 impl<
@@ -72,43 +88,97 @@ impl<
         BLACK_HAS_QUEEN_CASTLE_RIGHTS,
     >
 {
+    pub const fn new(pieces: PieceArrangement, en_passant: Option<Square>) -> Self {
+        debug_assert!(en_passant.is_some() == HAS_EP_PAWN);
+        Self {
+            pieces,
+            en_passant,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            castling_rooks: CastlingRooks::STANDARD,
+        }
+    }
+
+    /// Replace the clocks, which start at `0 1` for a board from [`Board::new`]
+    pub const fn with_clocks(self, halfmove_clock: u16, fullmove_number: u16) -> Self {
+        debug_assert!(fullmove_number > 0);
+        Self {
+            halfmove_clock,
+            fullmove_number,
+            ..self
+        }
+    }
+
+    /// Replace the rooks the castling rights refer to, which are the corners for a board from
+    /// [`Board::new`]
+    pub const fn with_castling_rooks(self, castling_rooks: CastlingRooks) -> Self {
+        Self {
+            castling_rooks,
+            ..self
+        }
+    }
+
+    /// Evaluate with `network` from now on, see [`PieceArrangement::with_network`]
+    pub fn with_network(self, network: Option<&'static Network>) -> Self {
+        Self {
+            pieces: self.pieces.with_network(network),
+            ..self
+        }
+    }
+
+    pub const fn castling_rooks(&self) -> CastlingRooks {
+        self.castling_rooks
+    }
+
+    pub const fn halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+
+    pub const fn fullmove_number(&self) -> u16 {
+        self.fullmove_number
+    }
+
+    /// The halfmove clock after a move by the side to move, `resets` for pawn moves and captures
+    const fn halfmove_clock_after(&self, resets: bool) -> u16 {
+        if resets {
+            0
+        } else {
+            self.halfmove_clock.saturating_add(1)
+        }
+    }
+
+    /// The fullmove number after a move by the side to move
+    const fn fullmove_number_after(&self) -> u16 {
+        self.fullmove_number + !IS_WHITE_TO_MOVE as u16
+    }
+
+    pub const fn pieces(&self) -> &PieceArrangement {
+        &self.pieces
+    }
+
+    /// The square a pawn that just double pushed skipped over, where it may be captured en passant
+    pub const fn en_passant_square(&self) -> Option<Square> {
+        if HAS_EP_PAWN {
+            self.en_passant
+        } else {
+            None
+        }
+    }
+
+    /// The FEN of this board, with the castling rights in X-FEN for Chess960 positions
     pub fn fen(self) -> String {
+        self.fen_with_castling(false)
+    }
+
+    /// The FEN of this board with every castling right given by its rook's file
+    pub fn shredder_fen(self) -> String {
+        self.fen_with_castling(true)
+    }
+
+    fn fen_with_castling(self, shredder: bool) -> String {
         let mut fen = String::with_capacity(128);
 
-        for (row_index, row) in SQUARES.chunks(8).rev().enumerate() {
-            let mut offset = 0;
-            for &sq in row {
-                if let Some(piece) = self.pieces.piece_type_on(sq) {
-                    if offset > 0 {
-                        fen.push_str(&offset.to_string());
-                        offset = 0;
-                    }
-                    let c = match (piece, self.pieces.player_on(sq).unwrap()) {
-                        (PieceType::Pawn, Player::Black) => 'p',
-                        (PieceType::Knight, Player::Black) => 'n',
-                        (PieceType::Bishop, Player::Black) => 'b',
-                        (PieceType::Rook, Player::Black) => 'r',
-                        (PieceType::Queen, Player::Black) => 'q',
-                        (PieceType::King, Player::Black) => 'k',
-                        (PieceType::Pawn, Player::White) => 'P',
-                        (PieceType::Knight, Player::White) => 'N',
-                        (PieceType::Bishop, Player::White) => 'B',
-                        (PieceType::Rook, Player::White) => 'R',
-                        (PieceType::Queen, Player::White) => 'Q',
-                        (PieceType::King, Player::White) => 'K',
-                    };
-                    fen.push(c);
-                } else {
-                    offset += 1;
-                }
-            }
-            if offset > 0 {
-                fen.push_str(&offset.to_string());
-            }
-            if row_index < 7 {
-                fen.push('/');
-            }
-        }
+        push_placement(&mut fen, &self.pieces);
 
         fen.push(' ');
         if IS_WHITE_TO_MOVE {
@@ -118,82 +188,138 @@ impl<
         }
 
         fen.push(' ');
-        if WHITE_HAS_KING_CASTLE_RIGHTS {
-            fen.push('K');
-        }
-        if WHITE_HAS_QUEEN_CASTLE_RIGHTS {
-            fen.push('Q');
-        }
-        if BLACK_HAS_KING_CASTLE_RIGHTS {
-            fen.push('k');
-        }
-        if BLACK_HAS_KING_CASTLE_RIGHTS {
-            fen.push('q');
-        }
-
-        if !WHITE_HAS_KING_CASTLE_RIGHTS
-            && !WHITE_HAS_QUEEN_CASTLE_RIGHTS
-            && !BLACK_HAS_KING_CASTLE_RIGHTS
-            && !BLACK_HAS_KING_CASTLE_RIGHTS
-        {
-            fen.push('-');
-        }
+        push_castling(
+            &mut fen,
+            &self.pieces,
+            self.castle_rights(),
+            self.castling_rooks,
+            shredder,
+        );
 
         fen.push(' ');
-        if HAS_EP_PAWN {
-            fen.push_str(todo!());
+        if let Some(en_passant) = self.en_passant_square() {
+            fen.push_str(&en_passant.to_string());
         } else {
             fen.push('-');
         }
 
         fen.push(' ');
-        fen.push('0');
+        fen.push_str(&self.halfmove_clock.to_string());
         fen.push(' ');
-        fen.push('1');
+        fen.push_str(&self.fullmove_number.to_string());
 
         fen
     }
 
+    /// Every square attacked by the side not to move
     pub const fn attacked(&self) -> Bitboard {
-        // TODO
-        EMPTY_BITBOARD
+        if IS_WHITE_TO_MOVE {
+            self.pieces.attacked_by::<false>()
+        } else {
+            self.pieces.attacked_by::<true>()
+        }
     }
 
     pub const fn is_white_to_move(&self) -> bool {
         IS_WHITE_TO_MOVE
     }
 
-    pub const fn can_castle<const CASTLE_DIRECTION: CastleDirection>(self) -> bool {
-        let attacked = self.attacked();
-        let occupied = self.pieces.occupied();
-        let rooks = self.pieces.mask_for_piece::<{ NonKingPieceType::Rook }>();
-
-        match (IS_WHITE_TO_MOVE, CASTLE_DIRECTION) {
-            (true, CastleDirection::KingSide) => {
-                WHITE_HAS_KING_CASTLE_RIGHTS
-                    && rooks & H1.to_bit() != EMPTY_BITBOARD
-                    && occupied & 0x60 == EMPTY_BITBOARD
-                    && attacked & 0x60 == EMPTY_BITBOARD
-            }
-            (true, CastleDirection::QueenSide) => {
-                WHITE_HAS_QUEEN_CASTLE_RIGHTS
-                    && rooks & H1.to_bit() != EMPTY_BITBOARD
-                    && occupied & 0xE == EMPTY_BITBOARD
-                    && attacked & 0xC == EMPTY_BITBOARD
-            }
-            (false, CastleDirection::KingSide) => {
-                BLACK_HAS_KING_CASTLE_RIGHTS
-                    && rooks & H1.to_bit() != EMPTY_BITBOARD
-                    && occupied & 0x6000_0000_0000_0000 == EMPTY_BITBOARD
-                    && attacked & 0x6000_0000_0000_0000 == EMPTY_BITBOARD
-            }
-            (false, CastleDirection::QueenSide) => {
-                BLACK_HAS_QUEEN_CASTLE_RIGHTS
-                    && rooks & H1.to_bit() != EMPTY_BITBOARD
-                    && occupied & 0x0E00_0000_0000_0000 == EMPTY_BITBOARD
-                    && attacked & 0x0C00_0000_0000_0000 == EMPTY_BITBOARD
+    pub const fn side_to_move(&self) -> Player {
+        if IS_WHITE_TO_MOVE {
+            Player::White
+        } else {
+            Player::Black
+        }
+    }
+
+    /// Castling rights indexed by [`Player`] then [`CastleDirection`]
+    pub const fn castle_rights(&self) -> [[bool; CastleDirection::COUNT]; Player::COUNT] {
+        [
+            [WHITE_HAS_KING_CASTLE_RIGHTS, WHITE_HAS_QUEEN_CASTLE_RIGHTS],
+            [BLACK_HAS_KING_CASTLE_RIGHTS, BLACK_HAS_QUEEN_CASTLE_RIGHTS],
+        ]
+    }
+
+    pub const fn has_castle_rights(&self, player: Player, direction: CastleDirection) -> bool {
+        self.castle_rights()[player as usize][direction as usize]
+    }
+
+    /// The Zobrist key for the side to move and castling rights, both fixed by the board's type
+    const STATE_ZOBRIST: u64 = state_key(
+        IS_WHITE_TO_MOVE,
+        [
+            [WHITE_HAS_KING_CASTLE_RIGHTS, WHITE_HAS_QUEEN_CASTLE_RIGHTS],
+            [BLACK_HAS_KING_CASTLE_RIGHTS, BLACK_HAS_QUEEN_CASTLE_RIGHTS],
+        ],
+    );
+
+    /// The Polyglot compatible Zobrist key of this position
+    ///
+    /// The pieces keep their part of the key up to date as every transition moves them, and the
+    /// type each transition returns brings the part for the side to move and castling rights. As
+    /// in Polyglot the en passant file only counts when a pawn stands ready to capture there.
+    pub const fn zobrist(&self) -> u64 {
+        let mut zobrist = self.pieces.zobrist() ^ Self::STATE_ZOBRIST;
+        if let Some(en_passant) = self.en_passant_square() {
+            let capturers = if IS_WHITE_TO_MOVE {
+                pawn_attacks::<false>(en_passant) & self.pieces.mask_for_player::<true>()
+            } else {
+                pawn_attacks::<true>(en_passant) & self.pieces.mask_for_player::<false>()
+            };
+            if capturers & self.pieces.mask_for_piece::<{ NonKingPieceType::Pawn }>()
+                != EMPTY_BITBOARD
+            {
+                zobrist ^= en_passant_key(en_passant.file());
             }
         }
+        zobrist
+    }
+
+    /// Whether the side to move may castle: it has the rights and the rook, every square either
+    /// piece crosses or lands on is empty but for the two of them, and the king is not in, passing
+    /// through, or landing in check
+    ///
+    /// This is the Chess960 rule, which reduces to the usual one when king and rooks start in the
+    /// corners and on the e file. Attacks are found with the castling rook out of the way, as a
+    /// rook that shields the king along the back rank stops doing so once it has castled.
+    pub const fn can_castle<const CASTLE_DIRECTION: CastleDirection>(self) -> bool {
+        let has_rights = match (IS_WHITE_TO_MOVE, CASTLE_DIRECTION) {
+            (true, CastleDirection::KingSide) => WHITE_HAS_KING_CASTLE_RIGHTS,
+            (true, CastleDirection::QueenSide) => WHITE_HAS_QUEEN_CASTLE_RIGHTS,
+            (false, CastleDirection::KingSide) => BLACK_HAS_KING_CASTLE_RIGHTS,
+            (false, CastleDirection::QueenSide) => BLACK_HAS_QUEEN_CASTLE_RIGHTS,
+        };
+        if !has_rights {
+            return false;
+        }
+
+        let player = self.side_to_move();
+        let king = self.pieces.king_square::<{ IS_WHITE_TO_MOVE }>();
+        let rook = self.castling_rooks.square(player, CASTLE_DIRECTION);
+        let king_to = CASTLE_DIRECTION.king_to(player);
+        let rook_to = CASTLE_DIRECTION.rook_to(player);
+        let rooks = self
+            .pieces
+            .mask_for_player_and_piece::<{ IS_WHITE_TO_MOVE }, { NonKingPieceType::Rook }>();
+        if rooks & rook.to_bit() == EMPTY_BITBOARD || king.rank() != rook.rank() {
+            return false;
+        }
+
+        let king_path = between(king, king_to) | king.to_bit() | king_to.to_bit();
+        let rook_path = between(rook, rook_to) | rook.to_bit() | rook_to.to_bit();
+        let occupied = self.pieces.occupied() & !king.to_bit() & !rook.to_bit();
+        if (king_path | rook_path) & occupied != EMPTY_BITBOARD {
+            return false;
+        }
+
+        let attacked = if IS_WHITE_TO_MOVE {
+            self.pieces
+                .attacked_by_with_occupancy::<false>(self.pieces.occupied() & !rook.to_bit())
+        } else {
+            self.pieces
+                .attacked_by_with_occupancy::<true>(self.pieces.occupied() & !rook.to_bit())
+        };
+        attacked & king_path == EMPTY_BITBOARD
     }
 
     pub const fn white_king_castle_after() -> bool {
@@ -219,22 +345,30 @@ impl<
         { Self::black_king_castle_after() },
         { Self::black_queen_castle_after() },
     > {
-        const ROOK_FROM_SQUARES: [[Square; 2]; 2] = [[H1, A1], [H8, A8]];
-        const ROOK_TO_SQUARES: [[Square; 2]; 2] = [[F1, D1], [F8, D8]];
-        const KING_FROM_SQUARES: [Square; 2] = [E1, E8];
-        const KING_TO_SQUARE: [[Square; 2]; 2] = [[G1, C1], [G8, C8]];
+        let player = self.side_to_move();
+        let king = self.pieces.king_square::<{ IS_WHITE_TO_MOVE }>();
+        let king_to = CASTLE_DIRECTION.king_to(player);
+        // In Chess960 the king may already stand where it lands, and either piece may land on
+        // the other's square, so the rook is lifted off the board first
+        let pieces = self
+            .pieces
+            .remove_piece::<{ IS_WHITE_TO_MOVE }, { NonKingPieceType::Rook }>(
+                self.castling_rooks.square(player, CASTLE_DIRECTION),
+            );
+        let pieces = if king as u8 == king_to as u8 {
+            pieces
+        } else {
+            pieces.move_piece::<{ IS_WHITE_TO_MOVE }, { PieceType::King }>(king, king_to)
+        };
 
         Board {
-            pieces: self
-                .pieces
-                .move_piece::<{ IS_WHITE_TO_MOVE }, { PieceType::Rook }>(
-                    ROOK_FROM_SQUARES[!IS_WHITE_TO_MOVE as usize][CASTLE_DIRECTION as usize],
-                    ROOK_TO_SQUARES[!IS_WHITE_TO_MOVE as usize][CASTLE_DIRECTION as usize],
-                )
-                .move_piece::<{ IS_WHITE_TO_MOVE }, { PieceType::King }>(
-                    KING_FROM_SQUARES[!IS_WHITE_TO_MOVE as usize],
-                    KING_TO_SQUARE[!IS_WHITE_TO_MOVE as usize][CASTLE_DIRECTION as usize],
-                ),
+            pieces: pieces.add_piece::<{ IS_WHITE_TO_MOVE }, { NonKingPieceType::Rook }>(
+                CASTLE_DIRECTION.rook_to(player),
+            ),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock_after(false),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
         }
     }
 
@@ -254,6 +388,13 @@ impl<
             pieces: self
                 .pieces
                 .move_by_squares::<{ IS_WHITE_TO_MOVE }>(from, to),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock_after(matches!(
+                self.pieces.piece_type_on(from),
+                Some(PieceType::Pawn)
+            )),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
         }
     }
 
@@ -274,23 +415,28 @@ impl<
                 .pieces
                 .remove_by_square::<{ !IS_WHITE_TO_MOVE }>(to)
                 .move_by_squares::<{ IS_WHITE_TO_MOVE }>(from, to),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock_after(true),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
         }
     }
 
+    /// Castling rights that survive capturing the opponent's rook on its `CASTLE_DIRECTION` corner
     pub const fn white_capture_king_rook_after<const CASTLE_DIRECTION: CastleDirection>() -> bool {
-        (IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::QueenSide))
+        (IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::KingSide))
             && WHITE_HAS_KING_CASTLE_RIGHTS
     }
     pub const fn white_capture_queen_rook_after<const CASTLE_DIRECTION: CastleDirection>() -> bool {
-        (IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::KingSide))
+        (IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::QueenSide))
             && WHITE_HAS_QUEEN_CASTLE_RIGHTS
     }
     pub const fn black_capture_king_rook_after<const CASTLE_DIRECTION: CastleDirection>() -> bool {
-        (!IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::QueenSide))
+        (!IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::KingSide))
             && BLACK_HAS_KING_CASTLE_RIGHTS
     }
     pub const fn black_capture_queen_rook_after<const CASTLE_DIRECTION: CastleDirection>() -> bool {
-        (!IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::KingSide))
+        (!IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::QueenSide))
             && BLACK_HAS_QUEEN_CASTLE_RIGHTS
     }
 
@@ -301,16 +447,20 @@ impl<
     ) -> Board<
         { !IS_WHITE_TO_MOVE },
         false,
-        { Self::white_king_rook_move_after::<CASTLE_DIRECTION>() },
-        { Self::white_queen_rook_move_after::<CASTLE_DIRECTION>() },
-        { Self::black_king_rook_move_after::<CASTLE_DIRECTION>() },
-        { Self::black_queen_rook_move_after::<CASTLE_DIRECTION>() },
+        { Self::white_capture_king_rook_after::<CASTLE_DIRECTION>() },
+        { Self::white_capture_queen_rook_after::<CASTLE_DIRECTION>() },
+        { Self::black_capture_king_rook_after::<CASTLE_DIRECTION>() },
+        { Self::black_capture_queen_rook_after::<CASTLE_DIRECTION>() },
     > {
         Board {
             pieces: self
                 .pieces
                 .remove_piece::<{ !IS_WHITE_TO_MOVE }, { NonKingPieceType::Rook }>(to)
                 .move_by_squares::<{ IS_WHITE_TO_MOVE }>(from, to),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock_after(true),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
         }
     }
 
@@ -331,12 +481,16 @@ impl<
                 .pieces
                 .remove_piece::<{ IS_WHITE_TO_MOVE }, { NonKingPieceType::Pawn }>(from)
                 .add_piece::<{ IS_WHITE_TO_MOVE }, { PROMOTION }>(to),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock_after(true),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
         }
     }
 
     pub const fn promote_capture<const PROMOTION: NonKingPieceType>(
         self,
-        _from: Square,
+        from: Square,
         to: Square,
     ) -> Board<
         { !IS_WHITE_TO_MOVE },
@@ -350,7 +504,12 @@ impl<
             pieces: self
                 .pieces
                 .remove_by_square::<{ !IS_WHITE_TO_MOVE }>(to)
+                .remove_piece::<{ IS_WHITE_TO_MOVE }, { NonKingPieceType::Pawn }>(from)
                 .add_piece::<{ IS_WHITE_TO_MOVE }, { PROMOTION }>(to),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock_after(true),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
         }
     }
 
@@ -359,21 +518,26 @@ impl<
         const CASTLE_DIRECTION: CastleDirection,
     >(
         self,
-        _from: Square,
+        from: Square,
         to: Square,
     ) -> Board<
         { !IS_WHITE_TO_MOVE },
         false,
-        { Self::white_king_rook_move_after::<CASTLE_DIRECTION>() },
-        { Self::white_queen_rook_move_after::<CASTLE_DIRECTION>() },
-        { Self::black_king_rook_move_after::<CASTLE_DIRECTION>() },
-        { Self::black_queen_rook_move_after::<CASTLE_DIRECTION>() },
+        { Self::white_capture_king_rook_after::<CASTLE_DIRECTION>() },
+        { Self::white_capture_queen_rook_after::<CASTLE_DIRECTION>() },
+        { Self::black_capture_king_rook_after::<CASTLE_DIRECTION>() },
+        { Self::black_capture_queen_rook_after::<CASTLE_DIRECTION>() },
     > {
         Board {
             pieces: self
                 .pieces
                 .remove_piece::<{ !IS_WHITE_TO_MOVE }, { NonKingPieceType::Rook }>(to)
+                .remove_piece::<{ IS_WHITE_TO_MOVE }, { NonKingPieceType::Pawn }>(from)
                 .add_piece::<{ IS_WHITE_TO_MOVE }, { PROMOTION }>(to),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock_after(true),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
         }
     }
 
@@ -393,27 +557,382 @@ impl<
             pieces: self
                 .pieces
                 .move_piece::<{ IS_WHITE_TO_MOVE }, { PieceType::King }>(from, to),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock_after(false),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
+        }
+    }
+
+    pub const fn king_capture(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Board<
+        { !IS_WHITE_TO_MOVE },
+        false,
+        { Self::white_king_castle_after() },
+        { Self::white_queen_castle_after() },
+        { Self::black_king_castle_after() },
+        { Self::black_queen_castle_after() },
+    > {
+        Board {
+            pieces: self
+                .pieces
+                .remove_by_square::<{ !IS_WHITE_TO_MOVE }>(to)
+                .move_piece::<{ IS_WHITE_TO_MOVE }, { PieceType::King }>(from, to),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock_after(true),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
+        }
+    }
+
+    // The `try_` transitions check the pieces are where the transition expects them and fail
+    // rather than corrupt the arrangement or panic. Whether the move is legal is not checked.
+
+    /// [`Board::quiet_move`] that fails unless `from` holds a piece of the side to move and `to`
+    /// is empty
+    pub const fn try_quiet_move(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            false,
+            { WHITE_HAS_KING_CASTLE_RIGHTS },
+            { WHITE_HAS_QUEEN_CASTLE_RIGHTS },
+            { BLACK_HAS_KING_CASTLE_RIGHTS },
+            { BLACK_HAS_QUEEN_CASTLE_RIGHTS },
+        >,
+        ArrangementError,
+    > {
+        match self.pieces.check_move::<{ IS_WHITE_TO_MOVE }>(from, to) {
+            Ok(()) => Ok(self.quiet_move(from, to)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`Board::capture`] that fails unless `from` holds a piece of the side to move and `to` one
+    /// of the other side's pieces besides its king
+    pub const fn try_capture(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            false,
+            { WHITE_HAS_KING_CASTLE_RIGHTS },
+            { WHITE_HAS_QUEEN_CASTLE_RIGHTS },
+            { BLACK_HAS_KING_CASTLE_RIGHTS },
+            { BLACK_HAS_QUEEN_CASTLE_RIGHTS },
+        >,
+        ArrangementError,
+    > {
+        if let Err(error) = self.pieces.own_piece_on::<{ IS_WHITE_TO_MOVE }>(from) {
+            return Err(error);
+        }
+        match self.pieces.check_remove::<{ !IS_WHITE_TO_MOVE }>(to) {
+            Ok(()) => Ok(self.capture(from, to)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Check `square` holds `piece` of the given side
+    const fn check_piece_on<const IS_WHITE: bool>(
+        &self,
+        square: Square,
+        piece: PieceType,
+    ) -> Result<(), ArrangementError> {
+        match self.pieces.own_piece_on::<{ IS_WHITE }>(square) {
+            Ok(found) if found as u8 == piece as u8 => Ok(()),
+            Ok(_) => Err(ArrangementError::WrongPieceType(square)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`Board::king_move`] that fails unless `from` holds the king of the side to move and `to`
+    /// is empty
+    pub const fn try_king_move(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            false,
+            { Self::white_king_castle_after() },
+            { Self::white_queen_castle_after() },
+            { Self::black_king_castle_after() },
+            { Self::black_queen_castle_after() },
+        >,
+        ArrangementError,
+    > {
+        if let Err(error) = self.check_piece_on::<{ IS_WHITE_TO_MOVE }>(from, PieceType::King) {
+            return Err(error);
+        }
+        match self.pieces.check_add(to) {
+            Ok(()) => Ok(self.king_move(from, to)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`Board::king_capture`] that fails unless `from` holds the king of the side to move and
+    /// `to` one of the other side's pieces besides its king
+    pub const fn try_king_capture(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            false,
+            { Self::white_king_castle_after() },
+            { Self::white_queen_castle_after() },
+            { Self::black_king_castle_after() },
+            { Self::black_queen_castle_after() },
+        >,
+        ArrangementError,
+    > {
+        if let Err(error) = self.check_piece_on::<{ IS_WHITE_TO_MOVE }>(from, PieceType::King) {
+            return Err(error);
+        }
+        match self.pieces.check_remove::<{ !IS_WHITE_TO_MOVE }>(to) {
+            Ok(()) => Ok(self.king_capture(from, to)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`Board::castle`] that fails unless the castling rook of the side to move is in place and
+    /// the squares the king and rook land on are empty or hold one of them
+    pub const fn try_castle<const CASTLE_DIRECTION: CastleDirection>(
+        self,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            false,
+            { Self::white_king_castle_after() },
+            { Self::white_queen_castle_after() },
+            { Self::black_king_castle_after() },
+            { Self::black_queen_castle_after() },
+        >,
+        ArrangementError,
+    > {
+        let player = self.side_to_move();
+        let king = self.pieces.king_square::<{ IS_WHITE_TO_MOVE }>();
+        let rook = self.castling_rooks.square(player, CASTLE_DIRECTION);
+        if let Err(error) = self.check_piece_on::<{ IS_WHITE_TO_MOVE }>(rook, PieceType::Rook) {
+            return Err(error);
+        }
+        if let Err(error) = self.check_landing(CASTLE_DIRECTION.king_to(player), king, rook) {
+            return Err(error);
+        }
+        match self.check_landing(CASTLE_DIRECTION.rook_to(player), king, rook) {
+            Ok(()) => Ok(self.castle::<CASTLE_DIRECTION>()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Check `square` is free for a castling king or rook, which may land on each other's square
+    const fn check_landing(
+        &self,
+        square: Square,
+        king: Square,
+        rook: Square,
+    ) -> Result<(), ArrangementError> {
+        if square as u8 == king as u8 || square as u8 == rook as u8 {
+            Ok(())
+        } else {
+            self.pieces.check_add(square)
+        }
+    }
+
+    /// [`Board::rook_move`] that fails unless `from` holds a rook of the side to move and `to` is
+    /// empty
+    pub const fn try_rook_move<const CASTLE_DIRECTION: CastleDirection>(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            false,
+            { Self::white_king_rook_move_after::<CASTLE_DIRECTION>() },
+            { Self::white_queen_rook_move_after::<CASTLE_DIRECTION>() },
+            { Self::black_king_rook_move_after::<CASTLE_DIRECTION>() },
+            { Self::black_queen_rook_move_after::<CASTLE_DIRECTION>() },
+        >,
+        ArrangementError,
+    > {
+        if let Err(error) = self.check_piece_on::<{ IS_WHITE_TO_MOVE }>(from, PieceType::Rook) {
+            return Err(error);
+        }
+        match self.pieces.check_add(to) {
+            Ok(()) => Ok(self.rook_move::<CASTLE_DIRECTION>(from, to)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`Board::capture_rook`] that fails unless `from` holds a piece of the side to move and `to`
+    /// one of the other side's rooks
+    pub const fn try_capture_rook<const CASTLE_DIRECTION: CastleDirection>(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            false,
+            { Self::white_capture_king_rook_after::<CASTLE_DIRECTION>() },
+            { Self::white_capture_queen_rook_after::<CASTLE_DIRECTION>() },
+            { Self::black_capture_king_rook_after::<CASTLE_DIRECTION>() },
+            { Self::black_capture_queen_rook_after::<CASTLE_DIRECTION>() },
+        >,
+        ArrangementError,
+    > {
+        if let Err(error) = self.pieces.own_piece_on::<{ IS_WHITE_TO_MOVE }>(from) {
+            return Err(error);
+        }
+        match self.check_piece_on::<{ !IS_WHITE_TO_MOVE }>(to, PieceType::Rook) {
+            Ok(()) => Ok(self.capture_rook::<CASTLE_DIRECTION>(from, to)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`Board::double_pawn_push`] that fails unless `from` holds a pawn of the side to move on
+    /// its starting rank, and the two squares in front of it are empty with `to` the far one
+    pub const fn try_double_pawn_push(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            true,
+            { WHITE_HAS_KING_CASTLE_RIGHTS },
+            { WHITE_HAS_QUEEN_CASTLE_RIGHTS },
+            { BLACK_HAS_KING_CASTLE_RIGHTS },
+            { BLACK_HAS_QUEEN_CASTLE_RIGHTS },
+        >,
+        ArrangementError,
+    > {
+        if let Err(error) = self.check_piece_on::<{ IS_WHITE_TO_MOVE }>(from, PieceType::Pawn) {
+            return Err(error);
+        }
+        let (start_rank, to_rank) = if IS_WHITE_TO_MOVE { (1, 3) } else { (6, 4) };
+        if from.rank() != start_rank || to.rank() != to_rank || from.file() != to.file() {
+            return Err(ArrangementError::Unreachable(from));
+        }
+        if let Err(error) = self
+            .pieces
+            .check_add(SQUARES[(from as usize + to as usize) / 2])
+        {
+            return Err(error);
+        }
+        match self.pieces.check_add(to) {
+            Ok(()) => Ok(self.double_pawn_push(from, to)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`Board::promote`] that fails unless `from` holds a pawn of the side to move and `to` is
+    /// empty
+    pub const fn try_promote<const PROMOTION: NonKingPieceType>(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            false,
+            { WHITE_HAS_KING_CASTLE_RIGHTS },
+            { WHITE_HAS_QUEEN_CASTLE_RIGHTS },
+            { BLACK_HAS_KING_CASTLE_RIGHTS },
+            { BLACK_HAS_QUEEN_CASTLE_RIGHTS },
+        >,
+        ArrangementError,
+    > {
+        if let Err(error) = self.check_piece_on::<{ IS_WHITE_TO_MOVE }>(from, PieceType::Pawn) {
+            return Err(error);
+        }
+        match self.pieces.check_add(to) {
+            Ok(()) => Ok(self.promote::<PROMOTION>(from, to)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`Board::promote_capture`] that fails unless `from` holds a pawn of the side to move and
+    /// `to` one of the other side's pieces besides its king
+    pub const fn try_promote_capture<const PROMOTION: NonKingPieceType>(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            false,
+            { WHITE_HAS_KING_CASTLE_RIGHTS },
+            { WHITE_HAS_QUEEN_CASTLE_RIGHTS },
+            { BLACK_HAS_KING_CASTLE_RIGHTS },
+            { BLACK_HAS_QUEEN_CASTLE_RIGHTS },
+        >,
+        ArrangementError,
+    > {
+        if let Err(error) = self.check_piece_on::<{ IS_WHITE_TO_MOVE }>(from, PieceType::Pawn) {
+            return Err(error);
+        }
+        match self.pieces.check_remove::<{ !IS_WHITE_TO_MOVE }>(to) {
+            Ok(()) => Ok(self.promote_capture::<PROMOTION>(from, to)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`Board::promote_capture_rook`] that fails unless `from` holds a pawn of the side to move
+    /// and `to` one of the other side's rooks
+    pub const fn try_promote_capture_rook<
+        const PROMOTION: NonKingPieceType,
+        const CASTLE_DIRECTION: CastleDirection,
+    >(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            false,
+            { Self::white_capture_king_rook_after::<CASTLE_DIRECTION>() },
+            { Self::white_capture_queen_rook_after::<CASTLE_DIRECTION>() },
+            { Self::black_capture_king_rook_after::<CASTLE_DIRECTION>() },
+            { Self::black_capture_queen_rook_after::<CASTLE_DIRECTION>() },
+        >,
+        ArrangementError,
+    > {
+        if let Err(error) = self.check_piece_on::<{ IS_WHITE_TO_MOVE }>(from, PieceType::Pawn) {
+            return Err(error);
+        }
+        match self.check_piece_on::<{ !IS_WHITE_TO_MOVE }>(to, PieceType::Rook) {
+            Ok(()) => Ok(self.promote_capture_rook::<PROMOTION, CASTLE_DIRECTION>(from, to)),
+            Err(error) => Err(error),
         }
     }
 
+    /// Castling rights that survive moving our own rook off its `CASTLE_DIRECTION` corner
     pub const fn white_king_rook_move_after<const CASTLE_DIRECTION: CastleDirection>() -> bool {
-        !IS_WHITE_TO_MOVE
-            && !matches!(CASTLE_DIRECTION, CastleDirection::KingSide)
+        (!IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::KingSide))
             && WHITE_HAS_KING_CASTLE_RIGHTS
     }
     pub const fn white_queen_rook_move_after<const CASTLE_DIRECTION: CastleDirection>() -> bool {
-        !IS_WHITE_TO_MOVE
-            && !matches!(CASTLE_DIRECTION, CastleDirection::QueenSide)
+        (!IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::QueenSide))
             && WHITE_HAS_QUEEN_CASTLE_RIGHTS
     }
     pub const fn black_king_rook_move_after<const CASTLE_DIRECTION: CastleDirection>() -> bool {
-        IS_WHITE_TO_MOVE
-            && !matches!(CASTLE_DIRECTION, CastleDirection::KingSide)
+        (IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::KingSide))
             && BLACK_HAS_KING_CASTLE_RIGHTS
     }
     pub const fn black_queen_rook_move_after<const CASTLE_DIRECTION: CastleDirection>() -> bool {
-        IS_WHITE_TO_MOVE
-            && !matches!(CASTLE_DIRECTION, CastleDirection::QueenSide)
+        (IS_WHITE_TO_MOVE || !matches!(CASTLE_DIRECTION, CastleDirection::QueenSide))
             && BLACK_HAS_QUEEN_CASTLE_RIGHTS
     }
     pub const fn rook_move<const CASTLE_DIRECTION: CastleDirection>(
@@ -432,6 +951,10 @@ impl<
             pieces: self
                 .pieces
                 .move_piece::<{ IS_WHITE_TO_MOVE }, { PieceType::Rook }>(from, to),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock_after(false),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
         }
     }
 
@@ -447,11 +970,76 @@ impl<
         { BLACK_HAS_KING_CASTLE_RIGHTS },
         { BLACK_HAS_QUEEN_CASTLE_RIGHTS },
     > {
-        // TODO: track en passant square somehow
+        debug_assert!(from.file() == to.file() && from.rank().abs_diff(to.rank()) == 2);
         Board {
             pieces: self
                 .pieces
                 .move_piece::<{ IS_WHITE_TO_MOVE }, { PieceType::Pawn }>(from, to),
+            en_passant: Some(SQUARES[(from as usize + to as usize) / 2]),
+            halfmove_clock: self.halfmove_clock_after(true),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
+        }
+    }
+
+    pub const fn white_king_castle_after_removing<
+        const PLAYER: Player,
+        const CASTLE_DIRECTION: CastleDirection,
+    >() -> bool {
+        !matches!(
+            (PLAYER, CASTLE_DIRECTION),
+            (Player::White, CastleDirection::KingSide)
+        ) && WHITE_HAS_KING_CASTLE_RIGHTS
+    }
+    pub const fn white_queen_castle_after_removing<
+        const PLAYER: Player,
+        const CASTLE_DIRECTION: CastleDirection,
+    >() -> bool {
+        !matches!(
+            (PLAYER, CASTLE_DIRECTION),
+            (Player::White, CastleDirection::QueenSide)
+        ) && WHITE_HAS_QUEEN_CASTLE_RIGHTS
+    }
+    pub const fn black_king_castle_after_removing<
+        const PLAYER: Player,
+        const CASTLE_DIRECTION: CastleDirection,
+    >() -> bool {
+        !matches!(
+            (PLAYER, CASTLE_DIRECTION),
+            (Player::Black, CastleDirection::KingSide)
+        ) && BLACK_HAS_KING_CASTLE_RIGHTS
+    }
+    pub const fn black_queen_castle_after_removing<
+        const PLAYER: Player,
+        const CASTLE_DIRECTION: CastleDirection,
+    >() -> bool {
+        !matches!(
+            (PLAYER, CASTLE_DIRECTION),
+            (Player::Black, CastleDirection::QueenSide)
+        ) && BLACK_HAS_QUEEN_CASTLE_RIGHTS
+    }
+
+    /// Drop one castling right without making a move, for when a rook is captured or moved off
+    /// its square by a transition that does not already account for it
+    pub const fn remove_castle_rights<
+        const PLAYER: Player,
+        const CASTLE_DIRECTION: CastleDirection,
+    >(
+        self,
+    ) -> Board<
+        { IS_WHITE_TO_MOVE },
+        { HAS_EP_PAWN },
+        { Self::white_king_castle_after_removing::<PLAYER, CASTLE_DIRECTION>() },
+        { Self::white_queen_castle_after_removing::<PLAYER, CASTLE_DIRECTION>() },
+        { Self::black_king_castle_after_removing::<PLAYER, CASTLE_DIRECTION>() },
+        { Self::black_queen_castle_after_removing::<PLAYER, CASTLE_DIRECTION>() },
+    > {
+        Board {
+            pieces: self.pieces,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            castling_rooks: self.castling_rooks,
         }
     }
 
@@ -467,6 +1055,407 @@ impl<
     > {
         Board {
             pieces: self.pieces,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            castling_rooks: self.castling_rooks,
+        }
+    }
+}
+
+impl<
+        const IS_WHITE_TO_MOVE: bool,
+        const WHITE_HAS_KING_CASTLE_RIGHTS: bool,
+        const WHITE_HAS_QUEEN_CASTLE_RIGHTS: bool,
+        const BLACK_HAS_KING_CASTLE_RIGHTS: bool,
+        const BLACK_HAS_QUEEN_CASTLE_RIGHTS: bool,
+    >
+    Board<
+        { IS_WHITE_TO_MOVE },
+        true,
+        WHITE_HAS_KING_CASTLE_RIGHTS,
+        WHITE_HAS_QUEEN_CASTLE_RIGHTS,
+        BLACK_HAS_KING_CASTLE_RIGHTS,
+        BLACK_HAS_QUEEN_CASTLE_RIGHTS,
+    >
+{
+    /// Capture the pawn that just double pushed by moving onto the square it skipped
+    pub const fn en_passant_capture(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Board<
+        { !IS_WHITE_TO_MOVE },
+        false,
+        { WHITE_HAS_KING_CASTLE_RIGHTS },
+        { WHITE_HAS_QUEEN_CASTLE_RIGHTS },
+        { BLACK_HAS_KING_CASTLE_RIGHTS },
+        { BLACK_HAS_QUEEN_CASTLE_RIGHTS },
+    > {
+        // The captured pawn sits beside the capturing pawn, on the same rank it started from
+        let captured = Square::from_file_and_rank(to.file(), from.rank());
+        Board {
+            pieces: self
+                .pieces
+                .remove_piece::<{ !IS_WHITE_TO_MOVE }, { NonKingPieceType::Pawn }>(captured)
+                .move_piece::<{ IS_WHITE_TO_MOVE }, { PieceType::Pawn }>(from, to),
+            en_passant: None,
+            halfmove_clock: self.halfmove_clock_after(true),
+            fullmove_number: self.fullmove_number_after(),
+            castling_rooks: self.castling_rooks,
+        }
+    }
+
+    /// [`Board::en_passant_capture`] that fails unless there is an en passant square diagonally
+    /// in front of a pawn of the side to move on `from`, with the other side's pawn beside it
+    pub const fn try_en_passant_capture(
+        self,
+        from: Square,
+    ) -> Result<
+        Board<
+            { !IS_WHITE_TO_MOVE },
+            false,
+            { WHITE_HAS_KING_CASTLE_RIGHTS },
+            { WHITE_HAS_QUEEN_CASTLE_RIGHTS },
+            { BLACK_HAS_KING_CASTLE_RIGHTS },
+            { BLACK_HAS_QUEEN_CASTLE_RIGHTS },
+        >,
+        ArrangementError,
+    > {
+        if let Err(error) = self.check_piece_on::<{ IS_WHITE_TO_MOVE }>(from, PieceType::Pawn) {
+            return Err(error);
+        }
+        let Some(to) = self.en_passant_square() else {
+            return Err(ArrangementError::Unreachable(from));
+        };
+        let forward = if IS_WHITE_TO_MOVE { 1 } else { -1 };
+        if from.file().abs_diff(to.file()) != 1 || to.rank() as i8 - from.rank() as i8 != forward {
+            return Err(ArrangementError::Unreachable(from));
+        }
+        if let Err(error) = self.pieces.check_add(to) {
+            return Err(error);
+        }
+        let captured = Square::from_file_and_rank(to.file(), from.rank());
+        match self.check_piece_on::<{ !IS_WHITE_TO_MOVE }>(captured, PieceType::Pawn) {
+            Ok(()) => Ok(self.en_passant_capture(from, to)),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bitboard::Bitboard;
+    use crate::board::{Board, DEFAULT_BOARD};
+    use crate::castle_direction::CastleDirection;
+    use crate::fen::Position;
+    use crate::piece_arrangement::ArrangementError;
+    use crate::piece_type::{NonKingPieceType, PieceType};
+    use crate::player::Player;
+    use crate::square::Square::*;
+
+    #[test]
+    fn attacked_from_default_board() {
+        const ATTACKED: Bitboard = DEFAULT_BOARD.attacked();
+        assert_eq!(ATTACKED, 0x7EFF_FF00_0000_0000);
+        assert_eq!(
+            DEFAULT_BOARD.switch_sides().attacked(),
+            0x0000_0000_00FF_FF7E
+        );
+    }
+
+    #[test]
+    fn attacked_includes_open_lines() {
+        let board = DEFAULT_BOARD
+            .double_pawn_push(E2, E4)
+            .quiet_move(B7, B6)
+            .quiet_move(G1, F3)
+            .quiet_move(C8, A6);
+        let attacked = board.attacked();
+        for square in [B5, C4, D3, E2, F1] {
+            assert_ne!(attacked & square.to_bit(), 0, "{square:?}");
         }
+        assert_eq!(attacked & G1.to_bit(), 0);
+    }
+
+    #[test]
+    fn fen_after_double_pawn_push_has_en_passant_square() {
+        let board = DEFAULT_BOARD.double_pawn_push(E2, E4);
+        assert_eq!(board.en_passant_square(), Some(E3));
+        assert_eq!(
+            board.fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+        let board = board.quiet_move(G8, F6).quiet_move(E4, E5);
+        let board = board.double_pawn_push(D7, D5);
+        assert_eq!(board.en_passant_square(), Some(D6));
+        assert_eq!(
+            board.fen(),
+            "rnbqkb1r/ppp1pppp/5n2/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3"
+        );
+    }
+
+    #[test]
+    fn en_passant_capture_removes_the_passed_pawn() {
+        let board = DEFAULT_BOARD
+            .double_pawn_push(E2, E4)
+            .quiet_move(A7, A6)
+            .quiet_move(E4, E5)
+            .double_pawn_push(D7, D5)
+            .en_passant_capture(E5, D6);
+        assert_eq!(board.pieces().piece_type_on(D5), None);
+        assert_eq!(board.pieces().piece_type_on(E5), None);
+        assert_eq!(board.pieces().piece_type_on(D6), Some(PieceType::Pawn));
+        assert_eq!(board.en_passant_square(), None);
+        assert_eq!(
+            board.fen(),
+            "rnbqkbnr/1pp1pppp/p2P4/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3"
+        );
+
+        let board = board
+            .double_pawn_push(B7, B5)
+            .quiet_move(A2, A3)
+            .quiet_move(B5, B4)
+            .double_pawn_push(C2, C4)
+            .en_passant_capture(B4, C3);
+        assert_eq!(board.pieces().piece_type_on(C4), None);
+        assert_eq!(board.pieces().piece_type_on(C3), Some(PieceType::Pawn));
+        assert_eq!(board.pieces().player_on(C3), Some(Player::Black));
+    }
+
+    #[test]
+    fn fen_castling_rights() {
+        assert_eq!(
+            DEFAULT_BOARD.fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+        let board = DEFAULT_BOARD
+            .double_pawn_push(E2, E4)
+            .double_pawn_push(E7, E5)
+            .king_move(E1, E2)
+            .quiet_move(B8, C6);
+        assert_eq!(
+            board.fen(),
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/8/PPPPKPPP/RNBQ1BNR w kq - 2 3"
+        );
+    }
+
+    #[test]
+    fn can_castle_king_side_when_unattacked() {
+        let board = DEFAULT_BOARD
+            .double_pawn_push(E2, E4)
+            .double_pawn_push(E7, E5)
+            .quiet_move(G1, F3)
+            .quiet_move(B8, C6)
+            .quiet_move(F1, C4)
+            .quiet_move(G8, F6);
+        assert!(board.can_castle::<{ CastleDirection::KingSide }>());
+    }
+
+    #[test]
+    fn cannot_castle_king_side_through_check() {
+        let board = DEFAULT_BOARD
+            .double_pawn_push(E2, E4)
+            .quiet_move(B7, B6)
+            .quiet_move(G1, F3)
+            .quiet_move(C8, A6)
+            .quiet_move(G2, G3)
+            .quiet_move(B8, C6)
+            .quiet_move(F1, H3)
+            .quiet_move(G8, F6);
+        assert!(!board.can_castle::<{ CastleDirection::KingSide }>());
+    }
+
+    #[test]
+    fn rook_transitions_drop_the_right_castling_rights() {
+        let position: Position = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse().unwrap();
+        let board = Board::<true, false, true, true, true, true>::new(*position.pieces(), None);
+
+        let moved = board.rook_move::<{ CastleDirection::KingSide }>(H1, H2);
+        assert_eq!(moved.fen(), "r3k2r/8/8/8/8/8/7R/R3K3 b Qkq - 1 1");
+        // The capturing rook also left its corner, which capture_rook leaves to the caller
+        let captured = moved.capture_rook::<{ CastleDirection::QueenSide }>(A8, A1);
+        assert_eq!(captured.fen(), "4k2r/8/8/8/8/8/7R/r3K3 w kq - 0 2");
+        let removed =
+            captured.remove_castle_rights::<{ Player::Black }, { CastleDirection::QueenSide }>();
+        assert_eq!(removed.fen(), "4k2r/8/8/8/8/8/7R/r3K3 w k - 0 2");
+    }
+
+    #[test]
+    fn clocks_follow_moves() {
+        let board = DEFAULT_BOARD.quiet_move(G1, F3);
+        assert_eq!((board.halfmove_clock(), board.fullmove_number()), (1, 1));
+        let board = board.quiet_move(G8, F6);
+        assert_eq!((board.halfmove_clock(), board.fullmove_number()), (2, 2));
+        let board = board.quiet_move(E2, E3);
+        assert_eq!((board.halfmove_clock(), board.fullmove_number()), (0, 2));
+        let board = board
+            .quiet_move(B8, C6)
+            .quiet_move(F1, B5)
+            .quiet_move(F6, E4);
+        assert_eq!((board.halfmove_clock(), board.fullmove_number()), (3, 4));
+        let board = board.capture(B5, C6);
+        assert_eq!((board.halfmove_clock(), board.fullmove_number()), (0, 4));
+    }
+
+    #[test]
+    fn halfmove_clock_saturates() {
+        let board = DEFAULT_BOARD.with_clocks(u16::MAX, 1).quiet_move(G1, F3);
+        assert_eq!(board.halfmove_clock(), u16::MAX);
+    }
+
+    #[test]
+    fn with_clocks_sets_the_fen_clocks() {
+        assert_eq!(
+            DEFAULT_BOARD.with_clocks(12, 34).fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 12 34"
+        );
+    }
+
+    #[test]
+    fn try_transitions_refuse_misplaced_pieces() {
+        assert_eq!(
+            DEFAULT_BOARD.try_quiet_move(G1, F3),
+            Ok(DEFAULT_BOARD.quiet_move(G1, F3))
+        );
+        assert_eq!(
+            DEFAULT_BOARD.try_quiet_move(E3, E4),
+            Err(ArrangementError::EmptySquare(E3))
+        );
+        assert_eq!(
+            DEFAULT_BOARD.try_quiet_move(E7, E5),
+            Err(ArrangementError::WrongPlayer(E7))
+        );
+        assert_eq!(
+            DEFAULT_BOARD.try_capture(D1, D2),
+            Err(ArrangementError::WrongPlayer(D2))
+        );
+        assert_eq!(
+            DEFAULT_BOARD.try_king_move(D1, D3),
+            Err(ArrangementError::WrongPieceType(D1))
+        );
+        assert_eq!(
+            DEFAULT_BOARD.try_king_move(E1, E2),
+            Err(ArrangementError::Occupied(E2))
+        );
+        assert_eq!(
+            DEFAULT_BOARD.try_king_capture(E1, E8),
+            Err(ArrangementError::King(E8))
+        );
+    }
+
+    #[test]
+    fn try_castle_refuses_blocked_or_missing_pieces() {
+        let position: Position = "r3k2r/8/8/8/8/8/8/R3K3 w KQkq - 0 1".parse().unwrap();
+        let board = Board::<true, false, true, true, true, true>::new(*position.pieces(), None);
+        assert_eq!(
+            board.try_castle::<{ CastleDirection::QueenSide }>(),
+            Ok(board.castle::<{ CastleDirection::QueenSide }>())
+        );
+        assert_eq!(
+            board.try_castle::<{ CastleDirection::KingSide }>(),
+            Err(ArrangementError::EmptySquare(H1))
+        );
+        assert_eq!(
+            DEFAULT_BOARD.try_castle::<{ CastleDirection::KingSide }>(),
+            Err(ArrangementError::Occupied(G1))
+        );
+    }
+
+    #[test]
+    fn try_rook_move_refuses_other_pieces() {
+        assert_eq!(
+            DEFAULT_BOARD.try_rook_move::<{ CastleDirection::QueenSide }>(B1, B3),
+            Err(ArrangementError::WrongPieceType(B1))
+        );
+        assert_eq!(
+            DEFAULT_BOARD.try_rook_move::<{ CastleDirection::QueenSide }>(A1, A2),
+            Err(ArrangementError::Occupied(A2))
+        );
+    }
+
+    #[test]
+    fn try_capture_rook_refuses_other_pieces() {
+        assert_eq!(
+            DEFAULT_BOARD.try_capture_rook::<{ CastleDirection::KingSide }>(H1, H7),
+            Err(ArrangementError::WrongPieceType(H7))
+        );
+    }
+
+    #[test]
+    fn try_double_pawn_push_refuses_other_moves() {
+        assert_eq!(
+            DEFAULT_BOARD.try_double_pawn_push(E2, E4),
+            Ok(DEFAULT_BOARD.double_pawn_push(E2, E4))
+        );
+        assert_eq!(
+            DEFAULT_BOARD.try_double_pawn_push(E2, E5),
+            Err(ArrangementError::Unreachable(E2))
+        );
+        assert_eq!(
+            DEFAULT_BOARD.try_double_pawn_push(G1, G3),
+            Err(ArrangementError::WrongPieceType(G1))
+        );
+        let blocked = DEFAULT_BOARD.quiet_move(B1, C3).quiet_move(B8, C6);
+        assert_eq!(
+            blocked.try_double_pawn_push(C2, C4),
+            Err(ArrangementError::Occupied(C3))
+        );
+    }
+
+    #[test]
+    fn try_en_passant_capture_refuses_a_missing_pawn() {
+        let board = DEFAULT_BOARD
+            .double_pawn_push(E2, E4)
+            .quiet_move(A7, A6)
+            .quiet_move(E4, E5)
+            .double_pawn_push(D7, D5);
+        assert_eq!(
+            board.try_en_passant_capture(E5),
+            Ok(board.en_passant_capture(E5, D6))
+        );
+        assert_eq!(
+            board.try_en_passant_capture(B2),
+            Err(ArrangementError::Unreachable(B2))
+        );
+
+        let position: Position = "4k3/8/8/3P4/8/8/8/4K3 w - - 0 1".parse().unwrap();
+        let board =
+            Board::<true, true, false, false, false, false>::new(*position.pieces(), Some(E6));
+        assert_eq!(
+            board.try_en_passant_capture(D5),
+            Err(ArrangementError::EmptySquare(E5))
+        );
+    }
+
+    #[test]
+    fn try_promote_refuses_other_pieces() {
+        assert_eq!(
+            DEFAULT_BOARD.try_promote::<{ NonKingPieceType::Queen }>(G1, G3),
+            Err(ArrangementError::WrongPieceType(G1))
+        );
+        assert_eq!(
+            DEFAULT_BOARD.try_promote::<{ NonKingPieceType::Queen }>(E2, E7),
+            Err(ArrangementError::Occupied(E7))
+        );
+    }
+
+    #[test]
+    fn try_promote_capture_refuses_the_king() {
+        assert_eq!(
+            DEFAULT_BOARD.try_promote_capture::<{ NonKingPieceType::Queen }>(E2, E8),
+            Err(ArrangementError::King(E8))
+        );
+    }
+
+    #[test]
+    fn try_promote_capture_rook_refuses_other_pieces() {
+        assert_eq!(
+            DEFAULT_BOARD
+                .try_promote_capture_rook::<{ NonKingPieceType::Queen }, { CastleDirection::KingSide }>(
+                    E2, G8
+                ),
+            Err(ArrangementError::WrongPieceType(G8))
+        );
     }
 }
@@ -1,7 +1,8 @@
+use crate::attacks::between;
 use crate::bitboard::{Bitboard, EMPTY_BITBOARD};
-use crate::castle_direction::CastleDirection;
+use crate::castle_direction::{CastleDirection, CastlingRooks};
 use crate::player::Player;
-use crate::square::Square::{A1, A8, H1, H8};
+use crate::square::Square;
 
 use core::marker::ConstParamTy;
 
@@ -19,26 +20,27 @@ pub const DEFAULT_BOARD_STATUS: BoardStatus = BoardStatus {
 };
 
 impl BoardStatus {
-    const CASTLE_EMPTY: [[Bitboard; CastleDirection::COUNT]; Player::COUNT] =
-        [[0x60, 0xE], [0x6000_0000_0000_0000, 0x0E00_0000_0000_0000]];
-    const CASTLE_UNATTACKED: [[Bitboard; CastleDirection::COUNT]; Player::COUNT] =
-        [[0x60, 0xC], [0x6000_0000_0000_0000, 0x0C00_0000_0000_0000]];
-    const ROOK_MASKS: [[Bitboard; CastleDirection::COUNT]; Player::COUNT] =
-        [[H1.to_bit(), A1.to_bit()], [H8.to_bit(), A8.to_bit()]];
+    /// Whether the side to move, with its king on `king`, may castle with the rook
+    /// `castling_rooks` names, following the same Chess960 rule as [`crate::board::Board`]
     pub const fn can_castle<const CASTLE_DIRECTION: CastleDirection>(
         self,
+        castling_rooks: CastlingRooks,
+        king: Square,
         attacked: Bitboard,
         occupied: Bitboard,
         rooks: Bitboard,
     ) -> bool {
-        self.has_rights[self.side_to_move as usize][CASTLE_DIRECTION as usize]
-            && occupied & Self::CASTLE_EMPTY[self.side_to_move as usize][CASTLE_DIRECTION as usize]
+        let player = self.side_to_move;
+        let rook = castling_rooks.square(player, CASTLE_DIRECTION);
+        let king_to = CASTLE_DIRECTION.king_to(player);
+        let rook_to = CASTLE_DIRECTION.rook_to(player);
+        let king_path = between(king, king_to) | king.to_bit() | king_to.to_bit();
+        let rook_path = between(rook, rook_to) | rook.to_bit() | rook_to.to_bit();
+        self.has_rights[player as usize][CASTLE_DIRECTION as usize]
+            && (king_path | rook_path) & occupied & !king.to_bit() & !rook.to_bit()
                 == EMPTY_BITBOARD
-            && attacked
-                & Self::CASTLE_UNATTACKED[self.side_to_move as usize][CASTLE_DIRECTION as usize]
-                == EMPTY_BITBOARD
-            && rooks & Self::ROOK_MASKS[self.side_to_move as usize][CASTLE_DIRECTION as usize]
-                != EMPTY_BITBOARD
+            && attacked & king_path == EMPTY_BITBOARD
+            && rooks & rook.to_bit() != EMPTY_BITBOARD
     }
 
     pub const fn switch_sides(self) -> Self {
@@ -72,7 +74,6 @@ impl BoardStatus {
             side_to_move: self.side_to_move.switch(),
             has_ep_pawn: false,
             has_rights,
-            ..self
         }
     }
 
@@ -96,7 +97,6 @@ impl BoardStatus {
             side_to_move: self.side_to_move.switch(),
             has_ep_pawn: false,
             has_rights,
-            ..self
         }
     }
 }
@@ -105,8 +105,9 @@ impl BoardStatus {
 mod test {
     use crate::bitboard::{Bitboard, EMPTY_BITBOARD};
     use crate::board_status::BoardStatus;
-    use crate::castle_direction::CastleDirection;
+    use crate::castle_direction::{CastleDirection, CastlingRooks};
     use crate::player::Player;
+    use crate::square::Square::{A1, B1, C1, D1, E1, E8};
     use test_case::test_case;
 
     const ALL_RIGHTS_WHITE_TO_MOVE: BoardStatus = BoardStatus {
@@ -186,14 +187,40 @@ mod test {
         rooks: Bitboard,
         expected: bool,
     ) {
+        let rooks_at = CastlingRooks::STANDARD;
+        let king = match board_status.side_to_move {
+            Player::White => E1,
+            Player::Black => E8,
+        };
         let actual = if castle_direction == CastleDirection::KingSide {
-            board_status.can_castle::<{ CastleDirection::KingSide }>(attacked, occupied, rooks)
+            board_status.can_castle::<{ CastleDirection::KingSide }>(
+                rooks_at, king, attacked, occupied, rooks,
+            )
         } else {
-            board_status.can_castle::<{ CastleDirection::QueenSide }>(attacked, occupied, rooks)
+            board_status.can_castle::<{ CastleDirection::QueenSide }>(
+                rooks_at, king, attacked, occupied, rooks,
+            )
         };
         assert_eq!(
             actual, expected,
             "attacked = {attacked:X} occupied = {occupied:X} rooks = {rooks:X} {board_status:?}"
         );
     }
+
+    #[test]
+    fn can_castle_with_chess960_rooks() {
+        // King on b1 with the a1 rook: c1 and d1 must be free, and the b and c files unattacked
+        let rooks_at =
+            CastlingRooks::STANDARD.with_square(Player::White, CastleDirection::QueenSide, A1);
+        let (king, rooks) = (B1.to_bit(), A1.to_bit());
+        let can_castle = |attacked, occupied| {
+            ALL_RIGHTS_WHITE_TO_MOVE.can_castle::<{ CastleDirection::QueenSide }>(
+                rooks_at, B1, attacked, occupied, rooks,
+            )
+        };
+        assert!(can_castle(EMPTY_BITBOARD, king | rooks));
+        assert!(!can_castle(EMPTY_BITBOARD, king | rooks | D1.to_bit()));
+        assert!(!can_castle(C1.to_bit(), king | rooks));
+        assert!(can_castle(A1.to_bit() | D1.to_bit(), king | rooks));
+    }
 }
@@ -1,3 +1,6 @@
+use crate::player::Player;
+use crate::square::{Square, Square::*};
+
 use core::marker::ConstParamTy;
 
 /// The direction to castle in for either side
@@ -10,4 +13,52 @@ pub enum CastleDirection {
 
 impl CastleDirection {
     pub const COUNT: usize = 2;
+
+    /// Where `player`'s king lands, the g or c file whatever the variant
+    pub const fn king_to(self, player: Player) -> Square {
+        const KING_TO: [[Square; CastleDirection::COUNT]; Player::COUNT] = [[G1, C1], [G8, C8]];
+        KING_TO[player as usize][self as usize]
+    }
+
+    /// Where `player`'s rook lands, the f or d file whatever the variant
+    pub const fn rook_to(self, player: Player) -> Square {
+        const ROOK_TO: [[Square; CastleDirection::COUNT]; Player::COUNT] = [[F1, D1], [F8, D8]];
+        ROOK_TO[player as usize][self as usize]
+    }
+}
+
+/// The square of the rook each side castles with in each direction
+///
+/// These are the corners in standard chess and come from the castling field of the FEN in
+/// Chess960, where the king and rooks may start on any file of the back rank.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct CastlingRooks([[Square; CastleDirection::COUNT]; Player::COUNT]);
+
+impl CastlingRooks {
+    pub const STANDARD: Self = Self([[H1, A1], [H8, A8]]);
+
+    /// Indexed by [`Player`] then [`CastleDirection`]
+    pub const fn new(rooks: [[Square; CastleDirection::COUNT]; Player::COUNT]) -> Self {
+        Self(rooks)
+    }
+
+    pub const fn square(self, player: Player, direction: CastleDirection) -> Square {
+        self.0[player as usize][direction as usize]
+    }
+
+    pub const fn with_square(
+        mut self,
+        player: Player,
+        direction: CastleDirection,
+        square: Square,
+    ) -> Self {
+        self.0[player as usize][direction as usize] = square;
+        self
+    }
+}
+
+impl Default for CastlingRooks {
+    fn default() -> Self {
+        Self::STANDARD
+    }
 }
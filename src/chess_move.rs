@@ -0,0 +1,375 @@
+use crate::castle_direction::{CastleDirection, CastlingRooks};
+use crate::piece_type::PromotionPieceType;
+use crate::player::Player;
+use crate::square::{ParseSquareError, Square, SQUARES};
+
+use core::fmt::{self, Debug, Display, Formatter};
+use core::str::FromStr;
+
+/// What a move does beyond relocating the moving piece
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum MoveKind {
+    Quiet,
+    DoublePawnPush,
+    Castle(CastleDirection),
+    Capture,
+    EnPassant,
+    Promotion(PromotionPieceType),
+    PromotionCapture(PromotionPieceType),
+}
+
+impl MoveKind {
+    const QUIET: u16 = 0;
+    const DOUBLE_PAWN_PUSH: u16 = 1;
+    const KING_CASTLE: u16 = 2;
+    const QUEEN_CASTLE: u16 = 3;
+    const CAPTURE: u16 = 4;
+    const EN_PASSANT: u16 = 5;
+    /// Set for every promotion, the low two bits then hold the promotion piece
+    const PROMOTION: u16 = 8;
+
+    const fn to_flags(self) -> u16 {
+        match self {
+            MoveKind::Quiet => Self::QUIET,
+            MoveKind::DoublePawnPush => Self::DOUBLE_PAWN_PUSH,
+            MoveKind::Castle(CastleDirection::KingSide) => Self::KING_CASTLE,
+            MoveKind::Castle(CastleDirection::QueenSide) => Self::QUEEN_CASTLE,
+            MoveKind::Capture => Self::CAPTURE,
+            MoveKind::EnPassant => Self::EN_PASSANT,
+            MoveKind::Promotion(piece) => Self::PROMOTION | piece as u16,
+            MoveKind::PromotionCapture(piece) => Self::PROMOTION | Self::CAPTURE | piece as u16,
+        }
+    }
+
+    const fn from_flags(flags: u16) -> Self {
+        const PROMOTIONS: [PromotionPieceType; PromotionPieceType::COUNT] = [
+            PromotionPieceType::Knight,
+            PromotionPieceType::Bishop,
+            PromotionPieceType::Rook,
+            PromotionPieceType::Queen,
+        ];
+        match flags {
+            Self::QUIET => MoveKind::Quiet,
+            Self::DOUBLE_PAWN_PUSH => MoveKind::DoublePawnPush,
+            Self::KING_CASTLE => MoveKind::Castle(CastleDirection::KingSide),
+            Self::QUEEN_CASTLE => MoveKind::Castle(CastleDirection::QueenSide),
+            Self::CAPTURE => MoveKind::Capture,
+            Self::EN_PASSANT => MoveKind::EnPassant,
+            _ if flags & Self::CAPTURE != 0 => {
+                MoveKind::PromotionCapture(PROMOTIONS[(flags & 0b11) as usize])
+            }
+            _ => MoveKind::Promotion(PROMOTIONS[(flags & 0b11) as usize]),
+        }
+    }
+}
+
+/// A move for the side to move packed into 16 bits: 6 bits origin, 6 bits destination and
+/// 4 bits of [`MoveKind`] flags
+///
+/// Castling is encoded as the king's origin and destination, as in UCI.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Move(u16);
+
+impl Move {
+    const SQUARE_MASK: u16 = 0b11_1111;
+    const TO_SHIFT: u16 = 6;
+    const FLAGS_SHIFT: u16 = 12;
+
+    pub const fn new(from: Square, to: Square, kind: MoveKind) -> Self {
+        Self(from as u16 | (to as u16) << Self::TO_SHIFT | kind.to_flags() << Self::FLAGS_SHIFT)
+    }
+
+    pub const fn from(self) -> Square {
+        SQUARES[(self.0 & Self::SQUARE_MASK) as usize]
+    }
+
+    pub const fn to(self) -> Square {
+        SQUARES[((self.0 >> Self::TO_SHIFT) & Self::SQUARE_MASK) as usize]
+    }
+
+    pub const fn kind(self) -> MoveKind {
+        MoveKind::from_flags(self.0 >> Self::FLAGS_SHIFT)
+    }
+
+    pub const fn is_capture(self) -> bool {
+        (self.0 >> Self::FLAGS_SHIFT) & MoveKind::CAPTURE != 0
+    }
+
+    pub const fn is_promotion(self) -> bool {
+        (self.0 >> Self::FLAGS_SHIFT) & MoveKind::PROMOTION != 0
+    }
+
+    pub const fn promotion(self) -> Option<PromotionPieceType> {
+        match self.kind() {
+            MoveKind::Promotion(piece) | MoveKind::PromotionCapture(piece) => Some(piece),
+            _ => None,
+        }
+    }
+
+    /// The raw packed representation
+    pub const fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    /// Unpack a move previously packed by [`Move::to_bits`], `None` for bits no move packs to,
+    /// such as those of a corrupt transposition table entry
+    ///
+    /// The squares must suit the kind: pawn moves go one rank forward, two for a double push,
+    /// from the rank where that move is possible, and castling lands the king on the g or c file
+    /// of its back rank.
+    pub const fn from_bits(bits: u16) -> Option<Self> {
+        // The two flag values between en passant and the promotions are unused
+        if matches!(bits >> Self::FLAGS_SHIFT, 6 | 7) {
+            return None;
+        }
+        let mv = Self(bits);
+        let (from, to) = (mv.from(), mv.to());
+        let (files, ranks) = (from.file().abs_diff(to.file()), (from.rank(), to.rank()));
+        let valid = match mv.kind() {
+            MoveKind::Quiet | MoveKind::Capture => from as u8 != to as u8,
+            MoveKind::DoublePawnPush => files == 0 && matches!(ranks, (1, 3) | (6, 4)),
+            MoveKind::EnPassant => files == 1 && matches!(ranks, (4, 5) | (3, 2)),
+            MoveKind::Promotion(_) => files == 0 && matches!(ranks, (6, 7) | (1, 0)),
+            MoveKind::PromotionCapture(_) => files == 1 && matches!(ranks, (6, 7) | (1, 0)),
+            MoveKind::Castle(direction) => match ranks {
+                (0, 0) => to as u8 == direction.king_to(Player::White) as u8,
+                (7, 7) => to as u8 == direction.king_to(Player::Black) as u8,
+                _ => false,
+            },
+        };
+        if valid {
+            Some(mv)
+        } else {
+            None
+        }
+    }
+
+    pub const fn to_uci(self) -> UciMove {
+        UciMove {
+            from: self.from(),
+            to: self.to(),
+            promotion: self.promotion(),
+        }
+    }
+
+    /// The move in UCI notation as a Chess960 GUI writes it, with castling as the king taking
+    /// its own rook so it cannot be mistaken for a king move in any starting position
+    pub const fn to_uci_chess960(self, castling_rooks: CastlingRooks) -> UciMove {
+        match self.kind() {
+            MoveKind::Castle(direction) => {
+                let player = if self.from().rank() == 0 {
+                    Player::White
+                } else {
+                    Player::Black
+                };
+                UciMove {
+                    from: self.from(),
+                    to: castling_rooks.square(player, direction),
+                    promotion: None,
+                }
+            }
+            _ => self.to_uci(),
+        }
+    }
+}
+
+impl Debug for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Move({} {:?})", self.to_uci(), self.kind())
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.to_uci(), f)
+    }
+}
+
+/// A move in UCI long algebraic notation (`e2e4`, `e1g1`, `e7e8q`)
+///
+/// It carries no knowledge of the position, resolve it to a [`Move`] with
+/// [`MoveList::find_uci`](crate::move_list::MoveList::find_uci).
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct UciMove {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<PromotionPieceType>,
+}
+
+/// Why a string is not a UCI move
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ParseUciMoveError {
+    /// Not 4 characters, or 5 with a promotion
+    InvalidLength,
+    InvalidSquare(ParseSquareError),
+    /// The fifth character was not one of `nbrq`
+    InvalidPromotion(char),
+}
+
+impl Display for ParseUciMoveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseUciMoveError::InvalidLength => f.write_str("expected a move like e2e4 or e7e8q"),
+            ParseUciMoveError::InvalidSquare(err) => Display::fmt(err, f),
+            ParseUciMoveError::InvalidPromotion(c) => {
+                write!(f, "invalid promotion piece '{c}', expected one of nbrq")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseUciMoveError {}
+
+impl From<ParseSquareError> for ParseUciMoveError {
+    fn from(err: ParseSquareError) -> Self {
+        ParseUciMoveError::InvalidSquare(err)
+    }
+}
+
+impl FromStr for UciMove {
+    type Err = ParseUciMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_ascii() || !(4..=5).contains(&s.len()) {
+            return Err(ParseUciMoveError::InvalidLength);
+        }
+        let promotion = match s[4..].chars().next() {
+            None => None,
+            Some('n') => Some(PromotionPieceType::Knight),
+            Some('b') => Some(PromotionPieceType::Bishop),
+            Some('r') => Some(PromotionPieceType::Rook),
+            Some('q') => Some(PromotionPieceType::Queen),
+            Some(c) => return Err(ParseUciMoveError::InvalidPromotion(c)),
+        };
+
+        Ok(Self {
+            from: s[0..2].parse()?,
+            to: s[2..4].parse()?,
+            promotion,
+        })
+    }
+}
+
+impl Display for UciMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        match self.promotion {
+            None => Ok(()),
+            Some(PromotionPieceType::Knight) => f.write_str("n"),
+            Some(PromotionPieceType::Bishop) => f.write_str("b"),
+            Some(PromotionPieceType::Rook) => f.write_str("r"),
+            Some(PromotionPieceType::Queen) => f.write_str("q"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::any_board::AnyBoard;
+    use crate::castle_direction::CastleDirection;
+    use crate::chess_move::{Move, MoveKind, ParseUciMoveError, UciMove};
+    use crate::piece_type::PromotionPieceType;
+    use crate::square::{Square, Square::*, SQUARES};
+    use test_case::test_case;
+
+    const KINDS: [MoveKind; 14] = [
+        MoveKind::Quiet,
+        MoveKind::DoublePawnPush,
+        MoveKind::Castle(CastleDirection::KingSide),
+        MoveKind::Castle(CastleDirection::QueenSide),
+        MoveKind::Capture,
+        MoveKind::EnPassant,
+        MoveKind::Promotion(PromotionPieceType::Knight),
+        MoveKind::Promotion(PromotionPieceType::Bishop),
+        MoveKind::Promotion(PromotionPieceType::Rook),
+        MoveKind::Promotion(PromotionPieceType::Queen),
+        MoveKind::PromotionCapture(PromotionPieceType::Knight),
+        MoveKind::PromotionCapture(PromotionPieceType::Bishop),
+        MoveKind::PromotionCapture(PromotionPieceType::Rook),
+        MoveKind::PromotionCapture(PromotionPieceType::Queen),
+    ];
+
+    #[test]
+    fn fits_in_sixteen_bits() {
+        assert_eq!(size_of::<Move>(), 2);
+    }
+
+    #[test]
+    fn packing_round_trips() {
+        for from in SQUARES {
+            for to in [A1, E4, H8, from] {
+                for kind in KINDS {
+                    let mv = Move::new(from, to, kind);
+                    assert_eq!((mv.from(), mv.to(), mv.kind()), (from, to, kind));
+                }
+            }
+        }
+    }
+
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"; "kiwipete")]
+    #[test_case("4k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1"; "white promotions and en passant")]
+    #[test_case("4k3/8/8/8/8/8/1p6/N3K3 b - - 0 1"; "black promotions")]
+    #[test_case("rk6/8/8/8/8/8/8/RK6 b Aa - 0 1"; "chess960 castling")]
+    fn from_bits_accepts_legal_moves(fen: &str) {
+        let board: AnyBoard = fen.parse().unwrap();
+        for &mv in board.legal_moves().iter() {
+            assert_eq!(Move::from_bits(mv.to_bits()), Some(mv));
+        }
+    }
+
+    #[test_case(E2 as u16 | (E4 as u16) << 6 | 6 << 12; "unused flags 6")]
+    #[test_case(E2 as u16 | (E4 as u16) << 6 | 7 << 12; "unused flags 7")]
+    #[test_case(Move::new(E2, E2, MoveKind::Quiet).to_bits(); "null move")]
+    #[test_case(Move::new(E2, E5, MoveKind::DoublePawnPush).to_bits(); "long double push")]
+    #[test_case(Move::new(E4, F5, MoveKind::EnPassant).to_bits(); "en passant from the wrong rank")]
+    #[test_case(Move::new(E6, E7, MoveKind::Promotion(PromotionPieceType::Queen)).to_bits(); "promotion short of the last rank")]
+    #[test_case(Move::new(E7, F8, MoveKind::Promotion(PromotionPieceType::Queen)).to_bits(); "promotion push onto another file")]
+    #[test_case(Move::new(E1, H1, MoveKind::Castle(CastleDirection::KingSide)).to_bits(); "castle onto the rook")]
+    #[test_case(Move::new(E2, G2, MoveKind::Castle(CastleDirection::KingSide)).to_bits(); "castle off the back rank")]
+    fn from_bits_rejects_what_no_move_packs_to(bits: u16) {
+        assert_eq!(Move::from_bits(bits), None);
+    }
+
+    #[test]
+    fn capture_flags() {
+        for kind in KINDS {
+            let is_capture = matches!(
+                kind,
+                MoveKind::Capture | MoveKind::EnPassant | MoveKind::PromotionCapture(_)
+            );
+            assert_eq!(Move::new(E2, E4, kind).is_capture(), is_capture, "{kind:?}");
+        }
+    }
+
+    #[test_case(Move::new(E2, E4, MoveKind::DoublePawnPush), "e2e4"; "double push")]
+    #[test_case(Move::new(E1, G1, MoveKind::Castle(CastleDirection::KingSide)), "e1g1"; "castle")]
+    #[test_case(Move::new(E7, E8, MoveKind::Promotion(PromotionPieceType::Queen)), "e7e8q"; "queen promotion")]
+    #[test_case(Move::new(B2, A1, MoveKind::PromotionCapture(PromotionPieceType::Knight)), "b2a1n"; "knight promotion capture")]
+    fn formats_as_uci(mv: Move, expected: &str) {
+        assert_eq!(mv.to_string(), expected);
+    }
+
+    #[test_case("e2e4", E2, E4, None)]
+    #[test_case("e7e8q", E7, E8, Some(PromotionPieceType::Queen))]
+    #[test_case("a2a1r", A2, A1, Some(PromotionPieceType::Rook))]
+    fn parses_uci(uci: &str, from: Square, to: Square, promotion: Option<PromotionPieceType>) {
+        let parsed: UciMove = uci.parse().unwrap();
+        assert_eq!(
+            parsed,
+            UciMove {
+                from,
+                to,
+                promotion
+            }
+        );
+        assert_eq!(parsed.to_string(), uci);
+    }
+
+    #[test_case("e2e", ParseUciMoveError::InvalidLength; "too short")]
+    #[test_case("e7e8qq", ParseUciMoveError::InvalidLength; "too long")]
+    #[test_case("e7e8k", ParseUciMoveError::InvalidPromotion('k'); "king promotion")]
+    #[test_case("z2e4", ParseUciMoveError::InvalidSquare(crate::square::ParseSquareError); "bad square")]
+    fn rejects_invalid_uci(uci: &str, expected: ParseUciMoveError) {
+        assert_eq!(uci.parse::<UciMove>(), Err(expected));
+    }
+}
@@ -0,0 +1,697 @@
+use crate::any_board::AnyBoard;
+use crate::attacks::{
+    bishop_attacks, king_attacks, knight_attacks, pawn_attacks_mask, queen_attacks, rook_attacks,
+};
+use crate::bitboard::{squares, Bitboard, EMPTY_BITBOARD};
+use crate::nnue;
+use crate::piece_arrangement::PieceArrangement;
+use crate::piece_type::{NonKingPieceType, PieceType};
+use crate::player::Player;
+use crate::square::Square;
+
+use core::fmt::{self, Display, Formatter};
+use core::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+/// Centipawn value of each piece, indexed by [`PieceType`]; the king is priceless but counted as
+/// zero so it never changes the material balance
+///
+/// These are the flat values exchanges are judged by, the evaluation has its own in [`Weights`].
+pub const PIECE_VALUES: [i32; PieceType::COUNT] = [100, 320, 330, 500, 900, 0];
+
+pub const fn piece_value(piece: PieceType) -> i32 {
+    PIECE_VALUES[piece as usize]
+}
+
+/// A middlegame and an endgame score, blended by how much material is left
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Tapered {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Tapered {
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Self { mg, eg }
+    }
+
+    /// Interpolate between the endgame score at phase 0 and the middlegame score at
+    /// [`MAX_PHASE`]
+    pub const fn taper(self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for Tapered {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.mg + rhs.mg, self.eg + rhs.eg)
+    }
+}
+
+impl AddAssign for Tapered {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Tapered {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+
+impl Neg for Tapered {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.mg, -self.eg)
+    }
+}
+
+impl Mul<i32> for Tapered {
+    type Output = Self;
+
+    fn mul(self, rhs: i32) -> Self {
+        Self::new(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+/// The game phase with every piece but the pawns still on the board
+pub const MAX_PHASE: i32 = 24;
+/// How much each piece counts towards the game phase, indexed by [`NonKingPieceType`]
+const PHASE_WEIGHTS: [i32; NonKingPieceType::COUNT] = [0, 1, 1, 2, 4];
+
+const NON_KING_PIECES: [NonKingPieceType; NonKingPieceType::COUNT] = [
+    NonKingPieceType::Pawn,
+    NonKingPieceType::Knight,
+    NonKingPieceType::Bishop,
+    NonKingPieceType::Rook,
+    NonKingPieceType::Queen,
+];
+
+/// The pieces that get a mobility score and can attack the king zone
+const SLIDERS_AND_KNIGHTS: [NonKingPieceType; 4] = [
+    NonKingPieceType::Knight,
+    NonKingPieceType::Bishop,
+    NonKingPieceType::Rook,
+    NonKingPieceType::Queen,
+];
+
+/// Every tunable number in the evaluation
+///
+/// Scores are from the point of view of the side owning the piece or pawn in question.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Weights {
+    /// Indexed by [`NonKingPieceType`]
+    pub material: [Tapered; NonKingPieceType::COUNT],
+    /// Indexed by [`PieceType`] then square, as seen from white with the eighth rank first, so
+    /// the tables read like a board diagram
+    pub piece_squares: [[Tapered; Square::COUNT]; PieceType::COUNT],
+    /// Per safe square attacked, for knights, bishops, rooks and queens
+    pub mobility: [Tapered; 4],
+    /// Indexed by rank counted from the pawn's own side, 0 for the first
+    pub passed_pawn: [Tapered; 8],
+    pub isolated_pawn: Tapered,
+    /// Per pawn beyond the first on a file
+    pub doubled_pawn: Tapered,
+    pub backward_pawn: Tapered,
+    /// Per square of the king zone an enemy knight, bishop, rook or queen attacks
+    pub king_attack: [Tapered; 4],
+    pub bishop_pair: Tapered,
+    /// A rook on a file without pawns
+    pub rook_open_file: Tapered,
+    /// A rook on a file with only the opponent's pawns
+    pub rook_semi_open_file: Tapered,
+}
+
+/// Build a piece-square table from middlegame and endgame scores
+const fn piece_square_table(
+    mg: [i32; Square::COUNT],
+    eg: [i32; Square::COUNT],
+) -> [Tapered; Square::COUNT] {
+    let mut table = [Tapered::new(0, 0); Square::COUNT];
+    let mut square = 0;
+    while square < Square::COUNT {
+        table[square] = Tapered::new(mg[square], eg[square]);
+        square += 1;
+    }
+    table
+}
+
+#[rustfmt::skip]
+const PAWN_MG: [i32; Square::COUNT] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const PAWN_EG: [i32; Square::COUNT] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    40,  40,  40,  40,  40,  40,  40,  40,
+    25,  25,  25,  25,  25,  25,  25,  25,
+    12,  12,  12,  12,  12,  12,  12,  12,
+     5,   5,   5,   5,   5,   5,   5,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+     0,   0,   0,   0,   0,   0,   0,   0,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; Square::COUNT] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; Square::COUNT] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; Square::COUNT] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; Square::COUNT] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MG: [i32; Square::COUNT] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_EG: [i32; Square::COUNT] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+/// The weights [`evaluate`] uses
+pub const WEIGHTS: Weights = Weights {
+    material: [
+        Tapered::new(82, 94),
+        Tapered::new(337, 281),
+        Tapered::new(365, 297),
+        Tapered::new(477, 512),
+        Tapered::new(1025, 936),
+    ],
+    piece_squares: [
+        piece_square_table(PAWN_MG, PAWN_EG),
+        piece_square_table(KNIGHT, KNIGHT),
+        piece_square_table(BISHOP, BISHOP),
+        piece_square_table(ROOK, ROOK),
+        piece_square_table(QUEEN, QUEEN),
+        piece_square_table(KING_MG, KING_EG),
+    ],
+    mobility: [
+        Tapered::new(4, 4),
+        Tapered::new(5, 5),
+        Tapered::new(2, 4),
+        Tapered::new(1, 2),
+    ],
+    passed_pawn: [
+        Tapered::new(0, 0),
+        Tapered::new(5, 10),
+        Tapered::new(5, 15),
+        Tapered::new(10, 25),
+        Tapered::new(20, 45),
+        Tapered::new(35, 75),
+        Tapered::new(60, 120),
+        Tapered::new(0, 0),
+    ],
+    isolated_pawn: Tapered::new(-10, -15),
+    doubled_pawn: Tapered::new(-10, -20),
+    backward_pawn: Tapered::new(-8, -10),
+    king_attack: [
+        Tapered::new(-8, -2),
+        Tapered::new(-6, -2),
+        Tapered::new(-10, -3),
+        Tapered::new(-14, -4),
+    ],
+    bishop_pair: Tapered::new(30, 50),
+    rook_open_file: Tapered::new(25, 10),
+    rook_semi_open_file: Tapered::new(12, 5),
+};
+
+/// One part of the evaluation, reported separately by [`Trace`]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Term {
+    Material,
+    PieceSquares,
+    Mobility,
+    PawnStructure,
+    KingSafety,
+    BishopPair,
+    RookFiles,
+}
+
+impl Term {
+    pub const COUNT: usize = 7;
+    pub const ALL: [Term; Term::COUNT] = [
+        Term::Material,
+        Term::PieceSquares,
+        Term::Mobility,
+        Term::PawnStructure,
+        Term::KingSafety,
+        Term::BishopPair,
+        Term::RookFiles,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Term::Material => "Material",
+            Term::PieceSquares => "Piece squares",
+            Term::Mobility => "Mobility",
+            Term::PawnStructure => "Pawn structure",
+            Term::KingSafety => "King safety",
+            Term::BishopPair => "Bishop pair",
+            Term::RookFiles => "Rook files",
+        }
+    }
+}
+
+const FILE_A: Bitboard = 0x0101_0101_0101_0101;
+
+const fn file_mask(file: u8) -> Bitboard {
+    FILE_A << file
+}
+
+/// The files either side of `file`
+const fn adjacent_files(file: u8) -> Bitboard {
+    let mut files = EMPTY_BITBOARD;
+    if file > 0 {
+        files |= file_mask(file - 1);
+    }
+    if file < 7 {
+        files |= file_mask(file + 1);
+    }
+    files
+}
+
+/// Every square on a rank ahead of `square` from `player`'s side
+const fn ranks_ahead(square: Square, player: Player) -> Bitboard {
+    let rank = square.rank() as u32;
+    match player {
+        Player::White if rank < 7 => !0 << (8 * (rank + 1)),
+        Player::Black => (1 << (8 * rank)) - 1,
+        Player::White => EMPTY_BITBOARD,
+    }
+}
+
+const fn pawn_attacks_by(player: Player, pawns: Bitboard) -> Bitboard {
+    match player {
+        Player::White => pawn_attacks_mask::<true>(pawns),
+        Player::Black => pawn_attacks_mask::<false>(pawns),
+    }
+}
+
+/// `square` as seen from white, where a piece of `player` on it looks its score up in a table of
+/// [`Weights::piece_squares`]
+const fn table_index(square: Square, player: Player) -> usize {
+    match player {
+        Player::White => square as usize ^ 56,
+        Player::Black => square as usize,
+    }
+}
+
+/// `square`'s rank counted from `player`'s side of the board
+const fn relative_rank(square: Square, player: Player) -> usize {
+    match player {
+        Player::White => square.rank() as usize,
+        Player::Black => 7 - square.rank() as usize,
+    }
+}
+
+/// Attacks of a knight, bishop, rook or queen on `square`
+fn piece_attacks(piece: NonKingPieceType, square: Square, occupied: Bitboard) -> Bitboard {
+    match piece {
+        NonKingPieceType::Knight => knight_attacks(square),
+        NonKingPieceType::Bishop => bishop_attacks(square, occupied),
+        NonKingPieceType::Rook => rook_attacks(square, occupied),
+        NonKingPieceType::Queen => queen_attacks(square, occupied),
+        NonKingPieceType::Pawn => unreachable!("pawns are scored by their structure"),
+    }
+}
+
+/// Every term for `player`'s pieces, from `player`'s point of view
+fn side_terms(
+    pieces: &PieceArrangement,
+    player: Player,
+    weights: &Weights,
+) -> [Tapered; Term::COUNT] {
+    let mut terms = [Tapered::default(); Term::COUNT];
+    let ours = pieces.mask_for_side(player);
+    let theirs = pieces.mask_for_side(player.switch());
+    let occupied = pieces.occupied();
+    let pawns = pieces.mask_for_piece_type(NonKingPieceType::Pawn);
+    let (our_pawns, their_pawns) = (pawns & ours, pawns & theirs);
+
+    for piece in NON_KING_PIECES {
+        for square in squares(pieces.mask_for_piece_type(piece) & ours) {
+            terms[Term::Material as usize] += weights.material[piece as usize];
+            terms[Term::PieceSquares as usize] +=
+                weights.piece_squares[piece as usize][table_index(square, player)];
+        }
+    }
+    terms[Term::PieceSquares as usize] += weights.piece_squares[PieceType::King as usize]
+        [table_index(pieces.king_square_for_side(player), player)];
+
+    // Mobility counts squares not held by our own pieces or covered by their pawns, and the same
+    // attacks on the squares around their king count against their king's safety
+    let mobility_area = !ours & !pawn_attacks_by(player.switch(), their_pawns);
+    let their_king = pieces.king_square_for_side(player.switch());
+    let their_king_zone = king_attacks(their_king) | their_king.to_bit();
+    let mut attacks_on_their_king = Tapered::default();
+    for (index, piece) in SLIDERS_AND_KNIGHTS.into_iter().enumerate() {
+        for square in squares(pieces.mask_for_piece_type(piece) & ours) {
+            let attacks = piece_attacks(piece, square, occupied);
+            terms[Term::Mobility as usize] +=
+                weights.mobility[index] * (attacks & mobility_area).count_ones() as i32;
+            attacks_on_their_king +=
+                weights.king_attack[index] * (attacks & their_king_zone).count_ones() as i32;
+        }
+    }
+    // Kept as a penalty to the defender, whose safety it is, by the caller
+    terms[Term::KingSafety as usize] = -attacks_on_their_king;
+
+    let their_pawn_attacks = pawn_attacks_by(player.switch(), their_pawns);
+    for square in squares(our_pawns) {
+        let file = square.file();
+        let ahead = ranks_ahead(square, player);
+        let neighbours = adjacent_files(file);
+        let mut structure = Tapered::default();
+        if their_pawns & (neighbours | file_mask(file)) & ahead == EMPTY_BITBOARD {
+            structure += weights.passed_pawn[relative_rank(square, player)];
+        }
+        if our_pawns & neighbours == EMPTY_BITBOARD {
+            structure += weights.isolated_pawn;
+        } else {
+            // No pawn level or behind on a neighbouring file can come up to guard the square
+            // in front, which their pawns already attack
+            let stop = match player {
+                Player::White => square.to_bit() << 8,
+                Player::Black => square.to_bit() >> 8,
+            };
+            if our_pawns & neighbours & !ahead == EMPTY_BITBOARD && stop & their_pawn_attacks != 0 {
+                structure += weights.backward_pawn;
+            }
+        }
+        terms[Term::PawnStructure as usize] += structure;
+    }
+    for file in 0..8 {
+        let count = (our_pawns & file_mask(file)).count_ones() as i32;
+        if count > 1 {
+            terms[Term::PawnStructure as usize] += weights.doubled_pawn * (count - 1);
+        }
+    }
+
+    if (pieces.mask_for_piece_type(NonKingPieceType::Bishop) & ours).count_ones() >= 2 {
+        terms[Term::BishopPair as usize] = weights.bishop_pair;
+    }
+
+    for square in squares(pieces.mask_for_piece_type(NonKingPieceType::Rook) & ours) {
+        let file = file_mask(square.file());
+        if pawns & file == EMPTY_BITBOARD {
+            terms[Term::RookFiles as usize] += weights.rook_open_file;
+        } else if our_pawns & file == EMPTY_BITBOARD {
+            terms[Term::RookFiles as usize] += weights.rook_semi_open_file;
+        }
+    }
+
+    terms
+}
+
+/// How far from the endgame the position is, from 0 with only kings and pawns left up to
+/// [`MAX_PHASE`]
+pub fn phase(pieces: &PieceArrangement) -> i32 {
+    let phase: i32 = NON_KING_PIECES
+        .into_iter()
+        .map(|piece| {
+            PHASE_WEIGHTS[piece as usize] * pieces.mask_for_piece_type(piece).count_ones() as i32
+        })
+        .sum();
+    phase.min(MAX_PHASE)
+}
+
+/// Every term of the evaluation for each side, with the phase they were blended by
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Trace {
+    /// Indexed by [`Player`] then [`Term`], each from that player's point of view
+    pub terms: [[Tapered; Term::COUNT]; Player::COUNT],
+    pub phase: i32,
+    pub is_white_to_move: bool,
+}
+
+impl Trace {
+    /// White's score for `term` minus black's
+    pub fn term(&self, term: Term) -> Tapered {
+        self.terms[Player::White as usize][term as usize]
+            - self.terms[Player::Black as usize][term as usize]
+    }
+
+    /// The blended score from white's point of view
+    pub fn white_score(&self) -> i32 {
+        let total = Term::ALL
+            .into_iter()
+            .fold(Tapered::default(), |total, term| total + self.term(term));
+        total.taper(self.phase)
+    }
+
+    /// The blended score from the point of view of the side to move, as [`evaluate`] returns it
+    pub fn score(&self) -> i32 {
+        if self.is_white_to_move {
+            self.white_score()
+        } else {
+            -self.white_score()
+        }
+    }
+}
+
+/// A table of every term, in pawns from white's point of view
+impl Display for Trace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let pawns = |centipawns: i32| centipawns as f64 / 100.0;
+        let pair = |score: Tapered| format!("{:6.2} {:6.2}", pawns(score.mg), pawns(score.eg));
+
+        writeln!(
+            f,
+            "{:>15} | {:^13} | {:^13} | {:^13}",
+            "Term", "White", "Black", "Total"
+        )?;
+        writeln!(
+            f,
+            "{:>15} | {:>6} {:>6} | {:>6} {:>6} | {:>6} {:>6}",
+            "", "MG", "EG", "MG", "EG", "MG", "EG"
+        )?;
+        writeln!(f, "{:-<16}+{:-<15}+{:-<15}+{:-<14}", "", "", "", "")?;
+        for term in Term::ALL {
+            writeln!(
+                f,
+                "{:>15} | {} | {} | {}",
+                term.name(),
+                pair(self.terms[Player::White as usize][term as usize]),
+                pair(self.terms[Player::Black as usize][term as usize]),
+                pair(self.term(term)),
+            )?;
+        }
+        writeln!(f, "{:-<16}+{:-<15}+{:-<15}+{:-<14}", "", "", "", "")?;
+        writeln!(f, "Phase: {}/{MAX_PHASE}", self.phase)?;
+        write!(
+            f,
+            "Evaluation: {:+.2} (white side)",
+            pawns(self.white_score())
+        )
+    }
+}
+
+/// Break the evaluation of `board` with `weights` down into its terms
+pub fn trace_with(board: &AnyBoard, weights: &Weights) -> Trace {
+    let pieces = board.pieces();
+    let mut terms = [
+        side_terms(pieces, Player::White, weights),
+        side_terms(pieces, Player::Black, weights),
+    ];
+    // King safety was scored by the attacker, it belongs to the side whose king is attacked
+    let white_attacks = terms[Player::White as usize][Term::KingSafety as usize];
+    let black_attacks = terms[Player::Black as usize][Term::KingSafety as usize];
+    terms[Player::White as usize][Term::KingSafety as usize] = -black_attacks;
+    terms[Player::Black as usize][Term::KingSafety as usize] = -white_attacks;
+
+    Trace {
+        terms,
+        phase: phase(pieces),
+        is_white_to_move: board.is_white_to_move(),
+    }
+}
+
+/// [`trace_with`] the default [`WEIGHTS`]
+pub fn trace(board: &AnyBoard) -> Trace {
+    trace_with(board, &WEIGHTS)
+}
+
+/// Static evaluation in centipawns from the point of view of the side to move, with `weights`
+pub fn evaluate_with(board: &AnyBoard, weights: &Weights) -> i32 {
+    trace_with(board, weights).score()
+}
+
+/// Static evaluation in centipawns from the point of view of the side to move, by the network
+/// attached to the board if there is one and otherwise by the hand-crafted [`WEIGHTS`]
+pub fn evaluate(board: &AnyBoard) -> i32 {
+    nnue::evaluate(board.pieces(), board.side_to_move())
+        .unwrap_or_else(|| evaluate_with(board, &WEIGHTS))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::any_board::AnyBoard;
+    use crate::evaluation::{evaluate, trace, Tapered, Term, MAX_PHASE};
+    use crate::player::Player;
+    use test_case::test_case;
+
+    /// The same position with the colours swapped and the board flipped
+    fn mirror(fen: &str) -> String {
+        let mut fields = fen.split(' ');
+        let placement = fields
+            .next()
+            .unwrap()
+            .split('/')
+            .rev()
+            .map(|rank| {
+                rank.chars()
+                    .map(|c| {
+                        if c.is_ascii_uppercase() {
+                            c.to_ascii_lowercase()
+                        } else {
+                            c.to_ascii_uppercase()
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let side = if fields.next() == Some("w") { "b" } else { "w" };
+        format!("{placement} {side} - - 0 1")
+    }
+
+    fn eval(fen: &str) -> i32 {
+        evaluate(&fen.parse::<AnyBoard>().unwrap())
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"; "starting position")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"; "kiwipete")]
+    #[test_case("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"; "rook endgame")]
+    #[test_case("r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R b KQ - 0 8"; "queens gambit")]
+    fn colour_symmetric(fen: &str) {
+        assert_eq!(eval(fen), eval(&mirror(fen)), "{fen}");
+    }
+
+    #[test]
+    fn starting_position_is_balanced() {
+        assert_eq!(
+            eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            0
+        );
+    }
+
+    #[test]
+    fn side_to_move_point_of_view() {
+        let white = eval("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let black = eval("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1");
+        assert!(white > 800, "{white}");
+        assert_eq!(black, -white);
+    }
+
+    #[test]
+    fn phase_follows_material() {
+        let start = trace(&AnyBoard::default());
+        assert_eq!(start.phase, MAX_PHASE);
+        let pawns = trace(&"4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1".parse().unwrap());
+        assert_eq!(pawns.phase, 0);
+        let rooks = trace(&"r3k3/8/8/8/8/8/8/R3K2R w - - 0 1".parse().unwrap());
+        assert_eq!(rooks.phase, 6);
+    }
+
+    #[test_case("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1", Term::PawnStructure, true; "passed pawn")]
+    #[test_case("4k3/p7/8/8/8/8/P7/4K3 w - - 0 1", Term::PawnStructure, false; "blocked isolated pawn")]
+    #[test_case("4k3/1p6/8/8/8/8/PPP5/4K3 w - - 0 1", Term::PawnStructure, true; "pawn majority")]
+    #[test_case("4k3/pp6/8/8/8/P7/P7/4K3 w - - 0 1", Term::PawnStructure, false; "doubled pawns")]
+    #[test_case("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1", Term::BishopPair, true; "bishop pair")]
+    #[test_case("4k3/p7/8/8/8/8/1P6/R3K3 w - - 0 1", Term::RookFiles, true; "semi open file")]
+    #[test_case("6k1/5ppp/8/8/8/8/8/3QK3 w - - 0 1", Term::Mobility, true; "queen mobility")]
+    #[test_case("6k1/5ppp/8/6N1/7Q/8/8/4K3 w - - 0 1", Term::KingSafety, true; "attacked king")]
+    fn terms_favour_white(fen: &str, term: Term, favours_white: bool) {
+        let trace = trace(&fen.parse().unwrap());
+        let score = trace.term(term).taper(trace.phase);
+        assert_eq!(score > 0, favours_white, "{trace}");
+    }
+
+    #[test]
+    fn trace_adds_up() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1";
+        let board = fen.parse().unwrap();
+        let trace = trace(&board);
+        assert_eq!(trace.score(), evaluate(&board));
+        let material = trace.terms[Player::White as usize][Term::Material as usize];
+        assert_eq!(
+            material,
+            trace.terms[Player::Black as usize][Term::Material as usize]
+        );
+        assert_ne!(material, Tapered::default());
+
+        let table = trace.to_string();
+        assert_eq!(table.lines().count(), 3 + Term::COUNT + 3);
+        assert!(table.contains("Pawn structure"));
+        assert!(table.ends_with("(white side)"));
+    }
+}
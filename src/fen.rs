@@ -0,0 +1,582 @@
+use crate::castle_direction::{CastleDirection, CastlingRooks};
+use crate::piece_arrangement::PieceArrangement;
+use crate::piece_type::PieceType;
+use crate::player::Player;
+use crate::square::{Square, SQUARES};
+
+use core::cmp::Ordering;
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// A position whose side to move, castling rights and en passant square are only known at
+/// runtime, as read from a FEN string
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Position {
+    pieces: PieceArrangement,
+    side_to_move: Player,
+    /// Indexed by [`Player`] then [`CastleDirection`]
+    castle_rights: [[bool; CastleDirection::COUNT]; Player::COUNT],
+    castling_rooks: CastlingRooks,
+    en_passant: Option<Square>,
+    halfmove_clock: u16,
+    fullmove_number: u16,
+}
+
+impl Position {
+    /// Assemble a position from fields already known to be consistent, as
+    /// [`PositionBuilder::build`](crate::position_builder::PositionBuilder::build) checks them
+    pub(crate) const fn from_parts(
+        pieces: PieceArrangement,
+        side_to_move: Player,
+        castle_rights: [[bool; CastleDirection::COUNT]; Player::COUNT],
+        castling_rooks: CastlingRooks,
+        en_passant: Option<Square>,
+        halfmove_clock: u16,
+        fullmove_number: u16,
+    ) -> Self {
+        Self {
+            pieces,
+            side_to_move,
+            castle_rights,
+            castling_rooks,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+        }
+    }
+
+    pub const fn pieces(&self) -> &PieceArrangement {
+        &self.pieces
+    }
+
+    pub const fn side_to_move(&self) -> Player {
+        self.side_to_move
+    }
+
+    pub const fn has_castle_rights(&self, player: Player, direction: CastleDirection) -> bool {
+        self.castle_rights[player as usize][direction as usize]
+    }
+
+    /// The rooks the castling rights refer to, see [`CastlingRooks`]
+    pub const fn castling_rooks(&self) -> CastlingRooks {
+        self.castling_rooks
+    }
+
+    pub const fn en_passant_square(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    /// Halfmoves since the last capture or pawn move
+    pub const fn halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+
+    /// Starts at 1 and increments after every black move
+    pub const fn fullmove_number(&self) -> u16 {
+        self.fullmove_number
+    }
+
+    pub fn fen(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// The six space separated fields of a FEN string, and anything after them
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum FenField {
+    Placement,
+    SideToMove,
+    Castling,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveNumber,
+    /// Anything after the fullmove number
+    Trailing,
+}
+
+impl Display for FenField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FenField::Placement => "piece placement",
+            FenField::SideToMove => "side to move",
+            FenField::Castling => "castling rights",
+            FenField::EnPassant => "en passant square",
+            FenField::HalfmoveClock => "halfmove clock",
+            FenField::FullmoveNumber => "fullmove number",
+            FenField::Trailing => "trailing input",
+        })
+    }
+}
+
+/// A FEN string was malformed in `field`, starting at character `offset` of the whole string
+///
+/// A missing field is reported at the end of the string.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct FenError {
+    pub field: FenField,
+    pub offset: usize,
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid {} in FEN at character {}",
+            self.field, self.offset
+        )
+    }
+}
+
+impl std::error::Error for FenError {}
+
+const fn piece_char(player: Player, piece: PieceType) -> char {
+    let c = match piece {
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Bishop => 'b',
+        PieceType::Rook => 'r',
+        PieceType::Queen => 'q',
+        PieceType::King => 'k',
+    };
+    match player {
+        Player::White => c.to_ascii_uppercase(),
+        Player::Black => c,
+    }
+}
+
+const fn piece_from_char(c: char) -> Option<(Player, PieceType)> {
+    let player = if c.is_ascii_uppercase() {
+        Player::White
+    } else {
+        Player::Black
+    };
+    let piece = match c.to_ascii_lowercase() {
+        'p' => PieceType::Pawn,
+        'n' => PieceType::Knight,
+        'b' => PieceType::Bishop,
+        'r' => PieceType::Rook,
+        'q' => PieceType::Queen,
+        'k' => PieceType::King,
+        _ => return None,
+    };
+
+    Some((player, piece))
+}
+
+/// Append the piece placement field for `pieces`
+pub(crate) fn push_placement(fen: &mut String, pieces: &PieceArrangement) {
+    for (row_index, row) in SQUARES.chunks(8).rev().enumerate() {
+        let mut empty = 0;
+        for &square in row {
+            if let Some(piece) = pieces.piece_type_on(square) {
+                if empty > 0 {
+                    fen.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                fen.push(piece_char(pieces.player_on(square).unwrap(), piece));
+            } else {
+                empty += 1;
+            }
+        }
+        if empty > 0 {
+            fen.push_str(&empty.to_string());
+        }
+        if row_index < 7 {
+            fen.push('/');
+        }
+    }
+}
+
+/// The rook `K`, `Q`, `k` or `q` refers to: the outermost one on that side of the king on its back
+/// rank, or the corner when there is none
+fn outermost_rook(pieces: &PieceArrangement, player: Player, direction: CastleDirection) -> Square {
+    let back_rank = match player {
+        Player::White => 0,
+        Player::Black => 7,
+    };
+    let king = pieces.king_square_for_side(player);
+    let is_rook = |&&square: &&Square| {
+        pieces.piece_type_on(square) == Some(PieceType::Rook)
+            && pieces.player_on(square) == Some(player)
+    };
+    let rank = &SQUARES[back_rank as usize * 8..][..8];
+    let rook = match direction {
+        CastleDirection::KingSide => rank.iter().rev().take_while(|&&s| s != king).find(is_rook),
+        CastleDirection::QueenSide => rank.iter().take_while(|&&s| s != king).find(is_rook),
+    };
+    match rook {
+        Some(&rook) if king.rank() == back_rank => rook,
+        _ => CastlingRooks::STANDARD.square(player, direction),
+    }
+}
+
+/// Append the castling field, `-` when no rights remain
+///
+/// Rights are written as in X-FEN, with `KQkq` for the outermost rooks and the rook's file for
+/// any other, or with every rook's file as in Shredder-FEN when `shredder` is set.
+pub(crate) fn push_castling(
+    fen: &mut String,
+    pieces: &PieceArrangement,
+    castle_rights: [[bool; CastleDirection::COUNT]; Player::COUNT],
+    castling_rooks: CastlingRooks,
+    shredder: bool,
+) {
+    let len = fen.len();
+    for (player, rights) in [Player::White, Player::Black]
+        .into_iter()
+        .zip(castle_rights)
+    {
+        for ((direction, piece), has_rights) in [
+            (CastleDirection::KingSide, PieceType::King),
+            (CastleDirection::QueenSide, PieceType::Queen),
+        ]
+        .into_iter()
+        .zip(rights)
+        {
+            if !has_rights {
+                continue;
+            }
+            let rook = castling_rooks.square(player, direction);
+            if !shredder && rook == outermost_rook(pieces, player, direction) {
+                fen.push(piece_char(player, piece));
+            } else {
+                let file = (b'a' + rook.file()) as char;
+                fen.push(match player {
+                    Player::White => file.to_ascii_uppercase(),
+                    Player::Black => file,
+                });
+            }
+        }
+    }
+    if fen.len() == len {
+        fen.push('-');
+    }
+}
+
+/// Parse the placement field, on failure returning the byte offset of the problem in `placement`
+fn parse_placement(placement: &str) -> Result<PieceArrangement, usize> {
+    let mut kings = [None; Player::COUNT];
+    let mut placed = Vec::with_capacity(32);
+    let (mut file, mut rank) = (0, 7);
+    for (i, c) in placement.char_indices() {
+        match c {
+            '/' if file == 8 && rank > 0 => {
+                file = 0;
+                rank -= 1;
+            }
+            '1'..='8' if file + (c as u8 - b'0') <= 8 => file += c as u8 - b'0',
+            _ => {
+                let (player, piece) = piece_from_char(c).ok_or(i)?;
+                if file == 8 {
+                    return Err(i);
+                }
+                let square = Square::from_file_and_rank(file, rank);
+                match piece.to_non_king_piece_type() {
+                    Some(piece) => placed.push((player, piece, square)),
+                    None if kings[player as usize].is_none() => {
+                        kings[player as usize] = Some(square)
+                    }
+                    None => return Err(i),
+                }
+                file += 1;
+            }
+        }
+    }
+
+    match kings {
+        [Some(white_king), Some(black_king)] if file == 8 && rank == 0 => {
+            Ok(placed.into_iter().fold(
+                PieceArrangement::new(white_king, black_king),
+                |pieces, (player, piece, square)| pieces.add_piece_for(player, piece, square),
+            ))
+        }
+        _ => Err(placement.len()),
+    }
+}
+
+/// Parse the castling field, on failure returning the byte offset of the problem in `castling`
+///
+/// Besides `KQkq` this accepts the rook files of Shredder-FEN and X-FEN, where `HAha` are the
+/// standard rights. A file gives the castling direction by which side of the king it is on.
+fn parse_castling(
+    castling: &str,
+    pieces: &PieceArrangement,
+) -> Result<
+    (
+        [[bool; CastleDirection::COUNT]; Player::COUNT],
+        CastlingRooks,
+    ),
+    usize,
+> {
+    let mut castle_rights = [[false; CastleDirection::COUNT]; Player::COUNT];
+    let mut rooks = CastlingRooks::STANDARD;
+    if castling == "-" {
+        return Ok((castle_rights, rooks));
+    }
+    for (i, c) in castling.char_indices() {
+        let (player, direction, rook) = match (c, piece_from_char(c)) {
+            (_, Some((player, PieceType::King))) => {
+                let direction = CastleDirection::KingSide;
+                (player, direction, outermost_rook(pieces, player, direction))
+            }
+            (_, Some((player, PieceType::Queen))) => {
+                let direction = CastleDirection::QueenSide;
+                (player, direction, outermost_rook(pieces, player, direction))
+            }
+            ('A'..='H' | 'a'..='h', _) => {
+                let (player, rank) = if c.is_ascii_uppercase() {
+                    (Player::White, 0)
+                } else {
+                    (Player::Black, 7)
+                };
+                let file = c.to_ascii_lowercase() as u8 - b'a';
+                let king_file = pieces.king_square_for_side(player).file();
+                let direction = match file.cmp(&king_file) {
+                    Ordering::Greater => CastleDirection::KingSide,
+                    Ordering::Less => CastleDirection::QueenSide,
+                    Ordering::Equal => return Err(i),
+                };
+                (player, direction, Square::from_file_and_rank(file, rank))
+            }
+            _ => return Err(i),
+        };
+        let has_rights = &mut castle_rights[player as usize][direction as usize];
+        if *has_rights {
+            return Err(i);
+        }
+        *has_rights = true;
+        rooks = rooks.with_square(player, direction, rook);
+    }
+
+    Ok((castle_rights, rooks))
+}
+
+/// Whether a pawn of the side not to move has just skipped over `square`
+fn can_capture_en_passant(pieces: &PieceArrangement, side_to_move: Player, square: Square) -> bool {
+    let (rank, pushed_rank) = match side_to_move {
+        Player::White => (5, 4),
+        Player::Black => (2, 3),
+    };
+    let pushed = Square::from_file_and_rank(square.file(), pushed_rank);
+    square.rank() == rank
+        && pieces.piece_type_on(square).is_none()
+        && pieces.piece_type_on(pushed) == Some(PieceType::Pawn)
+        && pieces.player_on(pushed) == Some(side_to_move.switch())
+}
+
+impl FromStr for Position {
+    type Err = FenError;
+
+    /// Parse all six FEN fields
+    ///
+    /// The two clocks may be left out together, as in EPD, and then default to `0 1`. The en
+    /// passant square must be empty and on the rank a pawn of the side not to move skipped over,
+    /// with that pawn in front of it.
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        let error = |field, byte_offset: usize| FenError {
+            field,
+            offset: fen[..byte_offset].chars().count(),
+        };
+        let mut fields = fen
+            .split_ascii_whitespace()
+            .map(|field| (field.as_ptr() as usize - fen.as_ptr() as usize, field));
+        let mut next_field = |field| fields.next().ok_or_else(|| error(field, fen.len()));
+
+        let (offset, placement) = next_field(FenField::Placement)?;
+        let pieces =
+            parse_placement(placement).map_err(|i| error(FenField::Placement, offset + i))?;
+
+        let (offset, side_to_move) = next_field(FenField::SideToMove)?;
+        let side_to_move = match side_to_move {
+            "w" => Player::White,
+            "b" => Player::Black,
+            _ => return Err(error(FenField::SideToMove, offset)),
+        };
+
+        let (offset, castling) = next_field(FenField::Castling)?;
+        let (castle_rights, castling_rooks) =
+            parse_castling(castling, &pieces).map_err(|i| error(FenField::Castling, offset + i))?;
+
+        let (offset, en_passant) = next_field(FenField::EnPassant)?;
+        let en_passant = match en_passant {
+            "-" => None,
+            square => match square.parse::<Square>() {
+                Ok(square) if can_capture_en_passant(&pieces, side_to_move, square) => Some(square),
+                _ => return Err(error(FenField::EnPassant, offset)),
+            },
+        };
+
+        let (halfmove_clock, fullmove_number) = match next_field(FenField::HalfmoveClock) {
+            Err(_) => (0, 1),
+            Ok((offset, halfmove_clock)) => {
+                let halfmove_clock = halfmove_clock
+                    .parse()
+                    .map_err(|_| error(FenField::HalfmoveClock, offset))?;
+                let (offset, fullmove_number) = next_field(FenField::FullmoveNumber)?;
+                let fullmove_number = match fullmove_number.parse() {
+                    Ok(number) if number > 0 => number,
+                    _ => return Err(error(FenField::FullmoveNumber, offset)),
+                };
+                (halfmove_clock, fullmove_number)
+            }
+        };
+
+        if let Some((offset, _)) = fields.next() {
+            return Err(error(FenField::Trailing, offset));
+        }
+
+        Ok(Self {
+            pieces,
+            side_to_move,
+            castle_rights,
+            castling_rooks,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+        })
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut fen = String::with_capacity(128);
+        push_placement(&mut fen, &self.pieces);
+        fen.push_str(match self.side_to_move {
+            Player::White => " w ",
+            Player::Black => " b ",
+        });
+        push_castling(
+            &mut fen,
+            &self.pieces,
+            self.castle_rights,
+            self.castling_rooks,
+            false,
+        );
+        f.write_str(&fen)?;
+        match self.en_passant {
+            Some(square) => write!(f, " {square}")?,
+            None => f.write_str(" -")?,
+        }
+        write!(f, " {} {}", self.halfmove_clock, self.fullmove_number)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::board::DEFAULT_BOARD;
+    use crate::castle_direction::CastleDirection;
+    use crate::fen::{FenError, FenField, Position, STARTING_FEN};
+    use crate::piece_type::PieceType;
+    use crate::player::Player;
+    use crate::square::Square::*;
+    use test_case::test_case;
+
+    #[test]
+    fn parses_starting_position() {
+        let position: Position = STARTING_FEN.parse().unwrap();
+        assert_eq!(position.pieces(), DEFAULT_BOARD.pieces());
+        assert_eq!(position.side_to_move(), Player::White);
+        for player in [Player::White, Player::Black] {
+            for direction in [CastleDirection::KingSide, CastleDirection::QueenSide] {
+                assert!(position.has_castle_rights(player, direction));
+            }
+        }
+        assert_eq!(position.en_passant_square(), None);
+        assert_eq!(
+            (position.halfmove_clock(), position.fullmove_number()),
+            (0, 1)
+        );
+    }
+
+    #[test]
+    fn parses_every_field() {
+        let position: Position = "r3k2r/8/8/3pP3/8/8/8/R3K2R w Kq d6 3 42".parse().unwrap();
+        assert_eq!(position.pieces().piece_type_on(D5), Some(PieceType::Pawn));
+        assert_eq!(position.pieces().player_on(D5), Some(Player::Black));
+        assert_eq!(position.pieces().king_square::<true>(), E1);
+        assert_eq!(position.pieces().king_square::<false>(), E8);
+        assert!(position.has_castle_rights(Player::White, CastleDirection::KingSide));
+        assert!(!position.has_castle_rights(Player::White, CastleDirection::QueenSide));
+        assert!(!position.has_castle_rights(Player::Black, CastleDirection::KingSide));
+        assert!(position.has_castle_rights(Player::Black, CastleDirection::QueenSide));
+        assert_eq!(position.en_passant_square(), Some(D6));
+        assert_eq!(
+            (position.halfmove_clock(), position.fullmove_number()),
+            (3, 42)
+        );
+    }
+
+    #[test_case(STARTING_FEN; "starting position")]
+    #[test_case("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"; "kiwipete")]
+    #[test_case("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"; "rook endgame")]
+    #[test_case("rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"; "sicilian")]
+    #[test_case("4k3/8/8/8/3pP3/8/8/4K3 b - e3 17 60"; "black en passant")]
+    fn round_trips(fen: &str) {
+        assert_eq!(fen.parse::<Position>().unwrap().fen(), fen);
+    }
+
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"; "outermost rooks by letter")]
+    #[test_case("r2rk1rr/8/8/8/8/8/8/R2RK1RR w DGgd - 0 1", "r2rk1rr/8/8/8/8/8/8/R2RK1RR w GDgd - 0 1"; "inner rooks by file")]
+    #[test_case("r2rk1rr/8/8/8/8/8/8/R2RK1RR w HDka - 0 1", "r2rk1rr/8/8/8/8/8/8/R2RK1RR w KDkq - 0 1"; "mixed")]
+    fn writes_x_fen(fen: &str, expected: &str) {
+        assert_eq!(fen.parse::<Position>().unwrap().fen(), expected);
+    }
+
+    #[test]
+    fn reads_chess960_castling_rooks() {
+        let position: Position = "1r2k1r1/8/8/8/8/8/8/RR2K1R1 w KBk - 0 1".parse().unwrap();
+        let rooks = position.castling_rooks();
+        assert_eq!(rooks.square(Player::White, CastleDirection::KingSide), G1);
+        assert_eq!(rooks.square(Player::White, CastleDirection::QueenSide), B1);
+        assert_eq!(rooks.square(Player::Black, CastleDirection::KingSide), G8);
+        assert!(!position.has_castle_rights(Player::Black, CastleDirection::QueenSide));
+    }
+
+    #[test]
+    fn clocks_are_optional() {
+        let position: Position = "4k3/8/8/8/8/8/8/4K3 b -  -".parse().unwrap();
+        assert_eq!(position.fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test_case("", FenField::Placement, 0; "empty")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1", FenField::Placement, 34; "seven ranks")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR/8 w KQkq - 0 1", FenField::Placement, 43; "nine ranks")]
+    #[test_case("rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenField::Placement, 17; "rank too long")]
+    #[test_case("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenField::Placement, 18; "skip too long")]
+    #[test_case("rnbqkbnr/pppppppp/7/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenField::Placement, 19; "rank too short")]
+    #[test_case("rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenField::Placement, 13; "unknown piece")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1BNR w KQkq - 0 1", FenField::Placement, 43; "missing king")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBKKBNR w KQkq - 0 1", FenField::Placement, 39; "two kings")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3", FenField::SideToMove, 19; "missing side")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 x - - 0 1", FenField::SideToMove, 20; "invalid side")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w KZ - 0 1", FenField::Castling, 23; "invalid castling")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w E - 0 1", FenField::Castling, 22; "castling rook on the king file")]
+    #[test_case("4k3/8/8/8/8/8/8/R3K2R w KH - 0 1", FenField::Castling, 25; "same right by letter and file")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w KK - 0 1", FenField::Castling, 23; "repeated castling")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w -- - 0 1", FenField::Castling, 22; "doubled dash")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - e9 0 1", FenField::EnPassant, 24; "invalid en passant")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - e3 0 1", FenField::EnPassant, 24; "en passant for the wrong side")]
+    #[test_case("4k3/8/8/3P4/8/8/8/4K3 w - e6 0 1", FenField::EnPassant, 26; "en passant without a pawn")]
+    #[test_case("4k3/8/8/4P3/8/8/8/4K3 w - e6 0 1", FenField::EnPassant, 26; "en passant past an own pawn")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - x 1", FenField::HalfmoveClock, 26; "invalid halfmove clock")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - 0", FenField::FullmoveNumber, 27; "missing fullmove number")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - 0 0", FenField::FullmoveNumber, 28; "zero fullmove number")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - 0 1 x", FenField::Trailing, 30; "trailing input")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - é 0 1", FenField::EnPassant, 24; "non ascii")]
+    fn rejects_invalid(fen: &str, field: FenField, offset: usize) {
+        assert_eq!(fen.parse::<Position>(), Err(FenError { field, offset }));
+    }
+
+    #[test]
+    fn error_names_field_and_offset() {
+        let err = "4k3/8/8/8/8/8/8/4K3 x - - 0 1"
+            .parse::<Position>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid side to move in FEN at character 20"
+        );
+    }
+}
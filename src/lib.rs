@@ -0,0 +1,30 @@
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs, adt_const_params, portable_simd)]
+
+pub mod any_board;
+pub mod attacks;
+pub mod bitboard;
+pub mod board;
+pub mod board_status;
+pub mod castle_direction;
+pub mod chess_move;
+pub mod evaluation;
+pub mod fen;
+pub mod magic;
+pub mod move_generation;
+pub mod move_list;
+pub mod nnue;
+pub mod perft;
+pub mod piece_arrangement;
+pub mod piece_type;
+pub mod player;
+pub mod position_builder;
+pub mod san;
+pub mod search;
+pub mod see;
+pub mod square;
+pub mod time_management;
+pub mod transposition_table;
+pub mod tuning;
+pub mod uci;
+pub mod zobrist;
@@ -0,0 +1,301 @@
+use crate::attacks::{ray_attacks, BISHOP_DIRECTIONS, ROOK_DIRECTIONS};
+use crate::bitboard::{Bitboard, EMPTY_BITBOARD};
+use crate::square::{Square, SQUARES};
+
+/// Multiply-shift hashing parameters mapping a slider's relevant blockers into the attack table
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Magic {
+    /// Squares whose occupancy can change the attack set (the rays without their final edge square)
+    pub mask: Bitboard,
+    /// Multiplier hashing the masked occupancy into the top bits
+    pub factor: u64,
+    /// Start of this square's slice in the shared attack table
+    pub offset: usize,
+}
+
+const ROOK_SHIFT: u32 = 64 - 12;
+const BISHOP_SHIFT: u32 = 64 - 9;
+const ATTACK_TABLE_SIZE: usize = 88772;
+
+// Fixed shift "white" magics found by Volker Annuss, the slices of both pieces overlap in a shared table
+// http://www.talkchess.com/forum/viewtopic.php?p=727500&t=64790
+#[rustfmt::skip]
+const ROOK_FACTORS: [(u64, usize); Square::COUNT] = [
+    (0x0028_0077_ffeb_fffe, 26304),
+    (0x2004_0102_0109_7fff, 35520),
+    (0x0010_0200_1005_3fff, 38592),
+    (0x0040_0400_0800_4002,  8026),
+    (0x7fd0_0441_ffff_d003, 22196),
+    (0x4020_0088_87df_fffe, 80870),
+    (0x0040_0088_8847_ffff, 76747),
+    (0x0068_00fb_ff75_fffd, 30400),
+    (0x0000_2801_0113_ffff, 11115),
+    (0x0020_0402_01fc_ffff, 18205),
+    (0x007f_e800_42ff_ffe8, 53577),
+    (0x0000_1800_217f_ffe8, 62724),
+    (0x0000_1800_073f_ffe8, 34282),
+    (0x0000_1800_e05f_ffe8, 29196),
+    (0x0000_1800_602f_ffe8, 23806),
+    (0x0000_3000_2fff_ffa0, 49481),
+    (0x0030_0018_010b_ffff,  2410),
+    (0x0003_000c_0085_fffb, 36498),
+    (0x0004_0008_0201_0008, 24478),
+    (0x0004_0020_2002_0004, 10074),
+    (0x0001_0020_0200_2001, 79315),
+    (0x0001_0010_0080_1040, 51779),
+    (0x0000_0040_4000_8001, 13586),
+    (0x0000_0068_00cd_fff4, 19323),
+    (0x0040_2000_1008_0010, 70612),
+    (0x0000_0800_1004_0010, 83652),
+    (0x0004_0100_0802_0008, 63110),
+    (0x0000_0400_2020_0200, 34496),
+    (0x0002_0080_1010_0100, 84966),
+    (0x0000_0080_2001_0020, 54341),
+    (0x0000_0080_2020_0040, 60421),
+    (0x0000_8200_2000_4020, 86402),
+    (0x00ff_fd18_0030_0030, 50245),
+    (0x007f_ff7f_bfd4_0020, 76622),
+    (0x003f_ffbd_0018_0018, 84676),
+    (0x001f_ffde_8018_0018, 78757),
+    (0x000f_ffe0_bfe8_0018, 37346),
+    (0x0001_0000_8020_2001,   370),
+    (0x0003_fffb_ff98_0180, 42182),
+    (0x0001_fffd_ff90_00e0, 45385),
+    (0x00ff_fefe_ebff_d800, 61659),
+    (0x007f_fff7_ffc0_1400, 12790),
+    (0x003f_ffbf_e4ff_e800, 16762),
+    (0x001f_fff0_1fc0_3000,     0),
+    (0x000f_ffe7_f8bf_e800, 38380),
+    (0x0007_ffdf_df3f_f808, 11098),
+    (0x0003_fff8_5fff_a804, 21803),
+    (0x0001_fffd_75ff_a802, 39189),
+    (0x00ff_ffd7_ffeb_ffd8, 58628),
+    (0x007f_ff75_ff7f_bfd8, 44116),
+    (0x003f_ff86_3fbf_7fd8, 78357),
+    (0x001f_ffbf_dfd7_ffd8, 44481),
+    (0x000f_fff8_1028_0028, 64134),
+    (0x0007_ffd7_f7fe_ffd8, 41759),
+    (0x0003_fffc_0c48_0048,  1394),
+    (0x0001_ffff_afd7_ffd8, 40910),
+    (0x00ff_ffe4_ffdf_a3ba, 66516),
+    (0x007f_ffef_7ff3_d3da,  3897),
+    (0x003f_ffbf_dfef_f7fa,  3930),
+    (0x001f_ffef_f7fb_fc22, 72934),
+    (0x0000_0204_0800_1001, 72662),
+    (0x0007_fffe_ffff_77fd, 56325),
+    (0x0003_ffff_bf7d_feec, 66501),
+    (0x0001_ffff_9dff_a333, 14826),
+];
+
+#[rustfmt::skip]
+const BISHOP_FACTORS: [(u64, usize); Square::COUNT] = [
+    (0x007f_bfbf_bfbf_bfff,  5378),
+    (0x0000_a060_4010_07fc,  4093),
+    (0x0001_0040_0802_0000,  4314),
+    (0x0000_8060_0400_0000,  6587),
+    (0x0000_1004_0000_0000,  6491),
+    (0x0000_21c1_00b2_0000,  6330),
+    (0x0000_0400_4100_8000,  5609),
+    (0x0000_0fb0_203f_ff80, 22236),
+    (0x0000_0401_0040_1004,  6106),
+    (0x0000_0200_8020_0802,  5625),
+    (0x0000_0040_1020_2000, 16785),
+    (0x0000_0080_6004_0000, 16817),
+    (0x0000_0044_0200_0000,  6842),
+    (0x0000_0008_0100_8000,  7003),
+    (0x0000_07ef_e0bf_ff80,  4197),
+    (0x0000_0008_2082_0020,  7356),
+    (0x0000_4000_8080_8080,  4602),
+    (0x0002_1f01_0040_0808,  4538),
+    (0x0001_8000_c06f_3fff, 29531),
+    (0x0000_2582_0080_1000, 45393),
+    (0x0000_2400_8084_0000, 12420),
+    (0x0000_1800_0c03_fff8, 15763),
+    (0x0000_0a58_4020_8020,  5050),
+    (0x0000_0200_0820_8020,  4346),
+    (0x0000_8040_0081_0100,  6074),
+    (0x0001_0119_0080_2008,  7866),
+    (0x0000_8040_0081_0100, 32139),
+    (0x0001_0040_3c04_03ff, 57673),
+    (0x0007_8402_a880_2000, 55365),
+    (0x0000_1010_0080_4400, 15818),
+    (0x0000_0808_0010_4100,  5562),
+    (0x0000_4004_c008_2008,  6390),
+    (0x0001_0101_2000_8020,  7930),
+    (0x0000_8080_9a00_4010, 13329),
+    (0x0007_fefe_0881_0010,  7170),
+    (0x0003_ff0f_833f_c080, 27267),
+    (0x007f_e080_1900_3042, 53787),
+    (0x003f_ffef_ea00_3000,  5097),
+    (0x0000_1010_1000_2080,  6643),
+    (0x0000_8020_0508_0804,  6138),
+    (0x0000_8080_80a8_0040,  7418),
+    (0x0000_1041_0020_0040,  7898),
+    (0x0003_ffdf_7f83_3fc0, 42012),
+    (0x0000_0088_4045_0020, 57350),
+    (0x0000_7ffc_8018_0030, 22813),
+    (0x007f_ffdd_8014_0028, 56693),
+    (0x0002_0080_200a_0004,  5818),
+    (0x0000_1010_1010_0020,  7098),
+    (0x0007_ffdf_c180_5000,  4451),
+    (0x0003_ffef_e0c0_2200,  4709),
+    (0x0000_0008_2080_6000,  4794),
+    (0x0000_0000_0840_3000, 13364),
+    (0x0000_0001_0020_2000,  4570),
+    (0x0000_0040_4080_2000,  4282),
+    (0x0004_0100_4010_0400, 14964),
+    (0x0000_6020_6018_03f4,  4026),
+    (0x0003_ffdf_dfc2_8048,  4826),
+    (0x0000_0008_2082_0020,  7354),
+    (0x0000_0000_0820_8060,  4848),
+    (0x0000_0000_0080_8020, 15946),
+    (0x0000_0000_0100_2020, 14932),
+    (0x0000_0004_0100_2008, 16588),
+    (0x0000_0040_4040_4040,  6905),
+    (0x007f_ff9f_df7f_f813, 16076),
+];
+
+const RANK_1: Bitboard = 0xFF;
+const RANK_8: Bitboard = 0xFF00_0000_0000_0000;
+const FILE_A: Bitboard = 0x0101_0101_0101_0101;
+const FILE_H: Bitboard = 0x8080_8080_8080_8080;
+
+/// Board edges that never block a slider on `square`, as nothing lies beyond them
+const fn edges_beyond(square: usize) -> Bitboard {
+    let rank = RANK_1 << (8 * (square / 8));
+    let file = FILE_A << (square % 8);
+    ((RANK_1 | RANK_8) & !rank) | ((FILE_A | FILE_H) & !file)
+}
+
+const fn build_magics(
+    factors: &[(u64, usize); Square::COUNT],
+    directions: &[(i8, i8); 4],
+) -> [Magic; Square::COUNT] {
+    let mut magics = [Magic {
+        mask: EMPTY_BITBOARD,
+        factor: 0,
+        offset: 0,
+    }; Square::COUNT];
+    let mut square = 0;
+    while square < Square::COUNT {
+        let (factor, offset) = factors[square];
+        magics[square] = Magic {
+            mask: ray_attacks(SQUARES[square], EMPTY_BITBOARD, directions) & !edges_beyond(square),
+            factor,
+            offset,
+        };
+        square += 1;
+    }
+
+    magics
+}
+
+pub const ROOK_MAGICS: [Magic; Square::COUNT] = build_magics(&ROOK_FACTORS, &ROOK_DIRECTIONS);
+pub const BISHOP_MAGICS: [Magic; Square::COUNT] = build_magics(&BISHOP_FACTORS, &BISHOP_DIRECTIONS);
+
+const fn magic_index(magic: &Magic, occupied: Bitboard, shift: u32) -> usize {
+    (magic.factor.wrapping_mul(occupied & magic.mask) >> shift) as usize + magic.offset
+}
+
+/// Fill the slice of every square by walking all subsets of its mask (Carry-Rippler)
+const fn fill_attacks(
+    mut table: [Bitboard; ATTACK_TABLE_SIZE],
+    magics: &[Magic; Square::COUNT],
+    directions: &[(i8, i8); 4],
+    shift: u32,
+) -> [Bitboard; ATTACK_TABLE_SIZE] {
+    let mut square = 0;
+    while square < Square::COUNT {
+        let magic = &magics[square];
+        let mut subset = EMPTY_BITBOARD;
+        loop {
+            let attacks = ray_attacks(SQUARES[square], subset, directions);
+            let index = magic_index(magic, subset, shift);
+            assert!(table[index] == EMPTY_BITBOARD || table[index] == attacks);
+            table[index] = attacks;
+            subset = subset.wrapping_sub(magic.mask) & magic.mask;
+            if subset == EMPTY_BITBOARD {
+                break;
+            }
+        }
+        square += 1;
+    }
+
+    table
+}
+
+#[allow(long_running_const_eval)]
+static SLIDER_ATTACKS: [Bitboard; ATTACK_TABLE_SIZE] = fill_attacks(
+    fill_attacks(
+        [EMPTY_BITBOARD; ATTACK_TABLE_SIZE],
+        &BISHOP_MAGICS,
+        &BISHOP_DIRECTIONS,
+        BISHOP_SHIFT,
+    ),
+    &ROOK_MAGICS,
+    &ROOK_DIRECTIONS,
+    ROOK_SHIFT,
+);
+
+/// Look up the squares a rook on `square` attacks given the `occupied` blockers
+pub const fn rook_magic_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    SLIDER_ATTACKS[magic_index(&ROOK_MAGICS[square as usize], occupied, ROOK_SHIFT)]
+}
+
+/// Look up the squares a bishop on `square` attacks given the `occupied` blockers
+pub const fn bishop_magic_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    SLIDER_ATTACKS[magic_index(&BISHOP_MAGICS[square as usize], occupied, BISHOP_SHIFT)]
+}
+
+#[cfg(test)]
+mod test {
+    use crate::attacks::{ray_attacks, BISHOP_DIRECTIONS, ROOK_DIRECTIONS};
+    use crate::bitboard::Bitboard;
+    use crate::magic::{bishop_magic_attacks, rook_magic_attacks, BISHOP_MAGICS, ROOK_MAGICS};
+    use crate::square::{Square, Square::*, SQUARES};
+    use test_case::test_case;
+
+    #[test_case(A1, 0x0001_0101_0101_017E; "corner a1")]
+    #[test_case(D4, 0x0008_0808_7608_0800; "center d4")]
+    #[test_case(H8, 0x7E80_8080_8080_8000; "corner h8")]
+    fn rook_mask_scenarios(square: Square, expected: Bitboard) {
+        assert_eq!(ROOK_MAGICS[square as usize].mask, expected, "{square:?}");
+    }
+
+    #[test_case(A1, 0x0040_2010_0804_0200; "corner a1")]
+    #[test_case(D4, 0x0040_2214_0014_2200; "center d4")]
+    #[test_case(E1, 0x0000_0000_0244_2800; "edge e1")]
+    fn bishop_mask_scenarios(square: Square, expected: Bitboard) {
+        assert_eq!(BISHOP_MAGICS[square as usize].mask, expected, "{square:?}");
+    }
+
+    #[test]
+    fn magic_lookups_match_ray_walks() {
+        // Simple xorshift so the occupancies are reproducible without pulling in a rng
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        for _ in 0..2_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let occupied = state & (state >> 11);
+            for square in SQUARES {
+                assert_eq!(
+                    rook_magic_attacks(square, occupied),
+                    ray_attacks(square, occupied, &ROOK_DIRECTIONS),
+                    "rook {square:?} {occupied:X}"
+                );
+                assert_eq!(
+                    bishop_magic_attacks(square, occupied),
+                    ray_attacks(square, occupied, &BISHOP_DIRECTIONS),
+                    "bishop {square:?} {occupied:X}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lookups_are_usable_in_const_context() {
+        const ATTACKS: Bitboard = rook_magic_attacks(D4, D6.to_bit() | B4.to_bit());
+        assert_eq!(ATTACKS, 0x0000_0808_F608_0808);
+    }
+}
@@ -0,0 +1,562 @@
+use crate::attacks::{
+    between, bishop_attacks, king_attacks, knight_attacks, line, pawn_attacks, rook_attacks,
+};
+use crate::bitboard::{squares, Bitboard, EMPTY_BITBOARD};
+use crate::board::Board;
+use crate::castle_direction::CastleDirection;
+use crate::chess_move::{Move, MoveKind};
+use crate::move_list::MoveList;
+use crate::piece_arrangement::PieceArrangement;
+use crate::piece_type::{NonKingPieceType, PromotionPieceType};
+use crate::square::{Square, SQUARES};
+
+const ALL_SQUARES: Bitboard = !EMPTY_BITBOARD;
+const RANK_2: Bitboard = 0xFF00;
+const RANK_7: Bitboard = 0x00FF_0000_0000_0000;
+const BACK_RANKS: Bitboard = 0xFF00_0000_0000_00FF;
+
+/// Why the side to move has no legal moves
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Termination {
+    /// In check, the side to move has lost
+    Checkmate,
+    /// Not in check, the game is drawn
+    Stalemate,
+}
+
+/// Squares attacked by the opponent of the given side, with sliders blocked by `occupied`
+fn opponent_attacks<const IS_WHITE: bool>(
+    pieces: &PieceArrangement,
+    occupied: Bitboard,
+) -> Bitboard {
+    if IS_WHITE {
+        pieces.attacked_by_with_occupancy::<false>(occupied)
+    } else {
+        pieces.attacked_by_with_occupancy::<true>(occupied)
+    }
+}
+
+/// Pieces of the given side that are the only blocker between their king and an enemy slider
+fn pinned<const IS_WHITE: bool>(
+    pieces: &PieceArrangement,
+    king: Square,
+    occupied: Bitboard,
+) -> Bitboard {
+    let queens = pieces.mask_for_opponent_and_piece::<{ IS_WHITE }, { NonKingPieceType::Queen }>();
+    let rooks = pieces.mask_for_opponent_and_piece::<{ IS_WHITE }, { NonKingPieceType::Rook }>();
+    let bishops =
+        pieces.mask_for_opponent_and_piece::<{ IS_WHITE }, { NonKingPieceType::Bishop }>();
+    let snipers = (rook_attacks(king, EMPTY_BITBOARD) & (rooks | queens))
+        | (bishop_attacks(king, EMPTY_BITBOARD) & (bishops | queens));
+
+    let mut pinned = EMPTY_BITBOARD;
+    for sniper in squares(snipers) {
+        let blockers = between(king, sniper) & occupied;
+        if blockers.count_ones() == 1 {
+            pinned |= blockers;
+        }
+    }
+
+    pinned & pieces.mask_for_player::<{ IS_WHITE }>()
+}
+
+/// Push a move to each of `destinations`, as captures where they hold an enemy piece
+fn push_piece_moves(from: Square, destinations: Bitboard, them: Bitboard, moves: &mut MoveList) {
+    for to in squares(destinations) {
+        let kind = if them & to.to_bit() != EMPTY_BITBOARD {
+            MoveKind::Capture
+        } else {
+            MoveKind::Quiet
+        };
+        moves.push(Move::new(from, to, kind));
+    }
+}
+
+/// Push a pawn move, expanding it into every promotion when it reaches the back rank
+fn push_pawn_move(from: Square, to: Square, is_capture: bool, moves: &mut MoveList) {
+    if to.to_bit() & BACK_RANKS != EMPTY_BITBOARD {
+        for promotion in PromotionPieceType::ALL {
+            let kind = if is_capture {
+                MoveKind::PromotionCapture(promotion)
+            } else {
+                MoveKind::Promotion(promotion)
+            };
+            moves.push(Move::new(from, to, kind));
+        }
+    } else {
+        let kind = if is_capture {
+            MoveKind::Capture
+        } else {
+            MoveKind::Quiet
+        };
+        moves.push(Move::new(from, to, kind));
+    }
+}
+
+/// The square directly in front of a pawn of the given side
+const fn pawn_push_square<const IS_WHITE: bool>(from: Square) -> Square {
+    if IS_WHITE {
+        SQUARES[from as usize + 8]
+    } else {
+        SQUARES[from as usize - 8]
+    }
+}
+
+/// Every legal non-castling move for the given side
+fn generate_legal_moves<const IS_WHITE: bool>(
+    pieces: &PieceArrangement,
+    en_passant: Option<Square>,
+    moves: &mut MoveList,
+) {
+    let king = pieces.king_square::<{ IS_WHITE }>();
+    let us = pieces.mask_for_player::<{ IS_WHITE }>();
+    let them = pieces.mask_for_opponent::<{ IS_WHITE }>();
+    let occupied = us | them;
+    let checkers = pieces.attackers_to(king, occupied) & them;
+
+    // The king is removed so it cannot step back along the ray of a slider checking it
+    let danger = opponent_attacks::<IS_WHITE>(pieces, occupied ^ king.to_bit());
+    push_piece_moves(king, king_attacks(king) & !us & !danger, them, moves);
+
+    // Only the king can escape a double check
+    if checkers.count_ones() > 1 {
+        return;
+    }
+    let check_mask = if checkers == EMPTY_BITBOARD {
+        ALL_SQUARES
+    } else {
+        between(king, SQUARES[checkers.trailing_zeros() as usize]) | checkers
+    };
+    let pinned = pinned::<IS_WHITE>(pieces, king, occupied);
+    let targets = !us & check_mask;
+    // A pinned piece may only move along the line through its king and pinner
+    let pin_mask = |from: Square| {
+        if pinned & from.to_bit() != EMPTY_BITBOARD {
+            line(king, from)
+        } else {
+            ALL_SQUARES
+        }
+    };
+
+    // Knights can never move along a pin
+    let knights = pieces.mask_for_player_and_piece::<{ IS_WHITE }, { NonKingPieceType::Knight }>();
+    for from in squares(knights & !pinned) {
+        push_piece_moves(from, knight_attacks(from) & targets, them, moves);
+    }
+
+    let queens = pieces.mask_for_player_and_piece::<{ IS_WHITE }, { NonKingPieceType::Queen }>();
+    let bishops = pieces.mask_for_player_and_piece::<{ IS_WHITE }, { NonKingPieceType::Bishop }>();
+    for from in squares(bishops | queens) {
+        let destinations = bishop_attacks(from, occupied) & targets & pin_mask(from);
+        push_piece_moves(from, destinations, them, moves);
+    }
+    let rooks = pieces.mask_for_player_and_piece::<{ IS_WHITE }, { NonKingPieceType::Rook }>();
+    for from in squares(rooks | queens) {
+        let destinations = rook_attacks(from, occupied) & targets & pin_mask(from);
+        push_piece_moves(from, destinations, them, moves);
+    }
+
+    let pawns = pieces.mask_for_player_and_piece::<{ IS_WHITE }, { NonKingPieceType::Pawn }>();
+    let start_rank = if IS_WHITE { RANK_2 } else { RANK_7 };
+    for from in squares(pawns) {
+        let allowed = check_mask & pin_mask(from);
+        let single = pawn_push_square::<IS_WHITE>(from);
+        if occupied & single.to_bit() == EMPTY_BITBOARD {
+            if allowed & single.to_bit() != EMPTY_BITBOARD {
+                push_pawn_move(from, single, false, moves);
+            }
+            if start_rank & from.to_bit() != EMPTY_BITBOARD {
+                let double = pawn_push_square::<IS_WHITE>(single);
+                if (occupied | !allowed) & double.to_bit() == EMPTY_BITBOARD {
+                    moves.push(Move::new(from, double, MoveKind::DoublePawnPush));
+                }
+            }
+        }
+        for to in squares(pawn_attacks::<IS_WHITE>(from) & them & allowed) {
+            push_pawn_move(from, to, true, moves);
+        }
+    }
+
+    if let Some(to) = en_passant {
+        let captured = if IS_WHITE {
+            SQUARES[to as usize - 8]
+        } else {
+            SQUARES[to as usize + 8]
+        };
+        for from in squares(pawns) {
+            if pawn_attacks::<IS_WHITE>(from) & to.to_bit() == EMPTY_BITBOARD {
+                continue;
+            }
+            // Two pawns leave the rank at once, so pins and checks are verified on the board after
+            let occupied_after = occupied ^ from.to_bit() ^ to.to_bit() ^ captured.to_bit();
+            let attackers = pieces.attackers_to(king, occupied_after) & them & !captured.to_bit();
+            if attackers == EMPTY_BITBOARD {
+                moves.push(Move::new(from, to, MoveKind::EnPassant));
+            }
+        }
+    }
+}
+
+impl<
+        const IS_WHITE_TO_MOVE: bool,
+        const HAS_EP_PAWN: bool,
+        const WHITE_HAS_KING_CASTLE_RIGHTS: bool,
+        const WHITE_HAS_QUEEN_CASTLE_RIGHTS: bool,
+        const BLACK_HAS_KING_CASTLE_RIGHTS: bool,
+        const BLACK_HAS_QUEEN_CASTLE_RIGHTS: bool,
+    >
+    Board<
+        { IS_WHITE_TO_MOVE },
+        { HAS_EP_PAWN },
+        WHITE_HAS_KING_CASTLE_RIGHTS,
+        WHITE_HAS_QUEEN_CASTLE_RIGHTS,
+        BLACK_HAS_KING_CASTLE_RIGHTS,
+        BLACK_HAS_QUEEN_CASTLE_RIGHTS,
+    >
+{
+    /// The pieces giving check to the side to move
+    pub const fn checkers(&self) -> Bitboard {
+        let pieces = self.pieces();
+        pieces.attackers_to(
+            pieces.king_square::<{ IS_WHITE_TO_MOVE }>(),
+            pieces.occupied(),
+        ) & pieces.mask_for_opponent::<{ IS_WHITE_TO_MOVE }>()
+    }
+
+    pub const fn in_check(&self) -> bool {
+        self.checkers() != EMPTY_BITBOARD
+    }
+
+    /// The side to move's pieces that cannot leave the line between their king and an enemy
+    /// slider without exposing the king
+    pub fn pinned(&self) -> Bitboard {
+        let pieces = self.pieces();
+        pinned::<IS_WHITE_TO_MOVE>(
+            pieces,
+            pieces.king_square::<{ IS_WHITE_TO_MOVE }>(),
+            pieces.occupied(),
+        )
+    }
+
+    /// How the game has ended for want of legal moves, `None` while the side to move has one
+    pub fn termination(&self) -> Option<Termination> {
+        if !self.legal_moves().is_empty() {
+            None
+        } else if self.in_check() {
+            Some(Termination::Checkmate)
+        } else {
+            Some(Termination::Stalemate)
+        }
+    }
+
+    /// Every legal move for the side to move
+    pub fn legal_moves(&self) -> MoveList {
+        let mut moves = MoveList::new();
+        generate_legal_moves::<IS_WHITE_TO_MOVE>(
+            self.pieces(),
+            self.en_passant_square(),
+            &mut moves,
+        );
+
+        // Castles are encoded from the king's square to where it lands, which in Chess960 may
+        // be the same square
+        let king = self.pieces().king_square::<{ IS_WHITE_TO_MOVE }>();
+        let player = self.side_to_move();
+        if self.can_castle::<{ CastleDirection::KingSide }>() {
+            let direction = CastleDirection::KingSide;
+            moves.push(Move::new(
+                king,
+                direction.king_to(player),
+                MoveKind::Castle(direction),
+            ));
+        }
+        if self.can_castle::<{ CastleDirection::QueenSide }>() {
+            let direction = CastleDirection::QueenSide;
+            moves.push(Move::new(
+                king,
+                direction.king_to(player),
+                MoveKind::Castle(direction),
+            ));
+        }
+
+        moves
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::any_board::AnyBoard;
+    use crate::bitboard::squares;
+    use crate::board::{Board, DEFAULT_BOARD};
+    use crate::castle_direction::CastleDirection;
+    use crate::chess_move::{Move, MoveKind};
+    use crate::fen::Position;
+    use crate::move_generation::Termination;
+    use crate::piece_arrangement::PieceArrangement;
+    use crate::piece_type::PromotionPieceType;
+    use crate::square::{Square, Square::*};
+    use test_case::test_case;
+
+    /// Build the arrangement described by the placement field of a FEN string
+    fn pieces_from_placement(placement: &str) -> PieceArrangement {
+        *format!("{placement} w - - 0 1")
+            .parse::<Position>()
+            .unwrap()
+            .pieces()
+    }
+
+    fn moves_from(moves: &[Move], from: Square) -> Vec<Square> {
+        moves
+            .iter()
+            .filter(|mv| mv.from() == from)
+            .map(|mv| mv.to())
+            .collect()
+    }
+
+    #[test]
+    fn default_board_has_twenty_moves() {
+        assert_eq!(DEFAULT_BOARD.legal_moves().len(), 20);
+        assert_eq!(
+            DEFAULT_BOARD.double_pawn_push(E2, E4).legal_moves().len(),
+            20
+        );
+    }
+
+    #[test]
+    fn kiwipete_includes_both_castles() {
+        let pieces =
+            pieces_from_placement("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R");
+        let moves = Board::<true, false, true, true, true, true>::new(pieces, None).legal_moves();
+        assert_eq!(moves.len(), 48);
+        assert!(moves.contains(&Move::new(
+            E1,
+            G1,
+            MoveKind::Castle(CastleDirection::KingSide)
+        )));
+        assert!(moves.contains(&Move::new(
+            E1,
+            C1,
+            MoveKind::Castle(CastleDirection::QueenSide)
+        )));
+        let moves = Board::<false, false, true, true, true, true>::new(pieces, None).legal_moves();
+        assert!(moves.contains(&Move::new(
+            E8,
+            G8,
+            MoveKind::Castle(CastleDirection::KingSide)
+        )));
+        assert!(moves.contains(&Move::new(
+            E8,
+            C8,
+            MoveKind::Castle(CastleDirection::QueenSide)
+        )));
+    }
+
+    #[test_case("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8", 14; "rook endgame with pins")]
+    #[test_case("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1", 6; "promotion heavy in check")]
+    #[test_case("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R", 43; "promotion captures without castling rights")]
+    fn white_to_move_counts(placement: &str, expected: usize) {
+        let pieces = pieces_from_placement(placement);
+        let moves =
+            Board::<true, false, false, false, false, false>::new(pieces, None).legal_moves();
+        assert_eq!(moves.len(), expected, "{moves:?}");
+    }
+
+    #[test]
+    fn black_to_move_counts() {
+        let pieces = pieces_from_placement("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R");
+        let moves =
+            Board::<false, false, true, true, false, false>::new(pieces, None).legal_moves();
+        assert_eq!(moves.len(), 6, "{moves:?}");
+    }
+
+    #[test]
+    fn resolves_uci_against_legal_moves() {
+        let moves = DEFAULT_BOARD.legal_moves();
+        let uci = |s: &str| moves.find_uci(s.parse().unwrap());
+        assert_eq!(
+            uci("e2e4"),
+            Some(Move::new(E2, E4, MoveKind::DoublePawnPush))
+        );
+        assert_eq!(uci("g1f3"), Some(Move::new(G1, F3, MoveKind::Quiet)));
+        assert_eq!(uci("e2e5"), None);
+        assert_eq!(uci("e2e4q"), None);
+
+        let pieces = pieces_from_placement("1n2k3/P7/8/8/8/8/8/4K2R");
+        let moves =
+            Board::<true, false, true, false, false, false>::new(pieces, None).legal_moves();
+        let uci = |s: &str| moves.find_uci(s.parse().unwrap());
+        assert_eq!(
+            uci("a7b8n"),
+            Some(Move::new(
+                A7,
+                B8,
+                MoveKind::PromotionCapture(PromotionPieceType::Knight)
+            ))
+        );
+        assert_eq!(
+            uci("e1g1"),
+            Some(Move::new(
+                E1,
+                G1,
+                MoveKind::Castle(CastleDirection::KingSide)
+            ))
+        );
+        assert_eq!(uci("a7a8"), None);
+    }
+
+    #[test]
+    fn en_passant_captures() {
+        let board = DEFAULT_BOARD
+            .double_pawn_push(E2, E4)
+            .quiet_move(A7, A6)
+            .quiet_move(E4, E5)
+            .double_pawn_push(D7, D5);
+        let moves = board.legal_moves();
+        assert_eq!(moves.len(), 31, "{moves:?}");
+        assert_eq!(
+            moves.find_uci("e5d6".parse().unwrap()),
+            Some(Move::new(E5, D6, MoveKind::EnPassant))
+        );
+
+        // Both sides of a double pushed pawn may capture it
+        let pieces = pieces_from_placement("4k3/8/8/8/2pPp3/8/8/4K3");
+        let moves =
+            Board::<false, true, false, false, false, false>::new(pieces, Some(D3)).legal_moves();
+        assert!(moves.contains(&Move::new(C4, D3, MoveKind::EnPassant)));
+        assert!(moves.contains(&Move::new(E4, D3, MoveKind::EnPassant)));
+    }
+
+    #[test]
+    fn en_passant_respects_pins_and_checks() {
+        // Capturing would remove both pawns from the fifth rank, exposing the king to the rook
+        let pieces = pieces_from_placement("8/8/8/KPp4r/8/8/8/7k");
+        let moves =
+            Board::<true, true, false, false, false, false>::new(pieces, Some(C6)).legal_moves();
+        assert!(
+            moves.iter().all(|mv| mv.kind() != MoveKind::EnPassant),
+            "{moves:?}"
+        );
+
+        // Diagonally pinned capturer
+        let pieces = pieces_from_placement("7k/b7/8/2Pp4/8/4K3/8/8");
+        let moves =
+            Board::<true, true, false, false, false, false>::new(pieces, Some(D6)).legal_moves();
+        assert!(
+            moves.iter().all(|mv| mv.kind() != MoveKind::EnPassant),
+            "{moves:?}"
+        );
+
+        // Capturing the checking pawn resolves the check
+        let pieces = pieces_from_placement("7k/8/8/3pP3/4K3/8/8/8");
+        let moves =
+            Board::<true, true, false, false, false, false>::new(pieces, Some(D6)).legal_moves();
+        assert!(
+            moves.contains(&Move::new(E5, D6, MoveKind::EnPassant)),
+            "{moves:?}"
+        );
+    }
+
+    #[test]
+    fn double_check_only_allows_king_moves() {
+        // Rook on e8 and knight on f3 both give check, only the king can move
+        let pieces = pieces_from_placement("4r2k/8/8/8/8/5n2/3Q4/4K3");
+        let moves =
+            Board::<true, false, false, false, false, false>::new(pieces, None).legal_moves();
+        assert!(moves.iter().all(|mv| mv.from() == E1), "{moves:?}");
+        assert_eq!(moves.len(), 3, "{moves:?}");
+    }
+
+    #[test]
+    fn pinned_pieces_stay_on_the_pin_line() {
+        let pieces = pieces_from_placement("4r2k/8/8/8/1b6/8/3NR3/4K3");
+        let moves =
+            Board::<true, false, false, false, false, false>::new(pieces, None).legal_moves();
+        assert!(moves_from(&moves, D2).is_empty(), "{moves:?}");
+        let mut rook_moves = moves_from(&moves, E2);
+        rook_moves.sort();
+        assert_eq!(rook_moves, vec![E3, E4, E5, E6, E7, E8]);
+    }
+
+    #[test]
+    fn check_must_be_blocked_or_captured() {
+        let pieces = pieces_from_placement("4k3/8/8/8/4N2b/8/3P4/R3K3");
+        let moves =
+            Board::<true, false, false, false, false, false>::new(pieces, None).legal_moves();
+        let mut knight_moves = moves_from(&moves, E4);
+        knight_moves.sort();
+        assert_eq!(knight_moves, vec![F2, G3]);
+        assert!(moves_from(&moves, A1).is_empty(), "{moves:?}");
+        assert!(moves_from(&moves, D2).is_empty(), "{moves:?}");
+    }
+
+    #[test]
+    fn promotions_expand_to_every_piece() {
+        let pieces = pieces_from_placement("1n2k3/P7/8/8/8/8/8/4K3");
+        let moves =
+            Board::<true, false, false, false, false, false>::new(pieces, None).legal_moves();
+        for promotion in PromotionPieceType::ALL {
+            assert!(moves.contains(&Move::new(A7, A8, MoveKind::Promotion(promotion))));
+            assert!(moves.contains(&Move::new(A7, B8, MoveKind::PromotionCapture(promotion))));
+        }
+    }
+
+    #[test]
+    fn cannot_castle_out_of_or_through_check() {
+        let castles = |placement| {
+            let pieces = pieces_from_placement(placement);
+            let moves =
+                Board::<true, false, true, true, false, false>::new(pieces, None).legal_moves();
+            moves
+                .iter()
+                .filter(|mv| matches!(mv.kind(), MoveKind::Castle(_)))
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        let king_side = Move::new(E1, G1, MoveKind::Castle(CastleDirection::KingSide));
+        let queen_side = Move::new(E1, C1, MoveKind::Castle(CastleDirection::QueenSide));
+
+        assert_eq!(
+            castles("4k3/8/8/8/8/8/8/R3K2R"),
+            vec![king_side, queen_side]
+        );
+        assert_eq!(castles("4k3/8/8/8/8/8/8/R3K2r"), vec![]);
+        assert_eq!(castles("4k3/8/8/8/8/8/5r2/R3K2R"), vec![queen_side]);
+        assert_eq!(castles("4k3/8/8/8/8/8/3r4/R3K2R"), vec![king_side]);
+        // Only the king's path matters, the b file may be attacked
+        assert_eq!(
+            castles("4k3/8/8/8/8/8/1r6/R3K2R"),
+            vec![king_side, queen_side]
+        );
+    }
+
+    #[test_case("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3", &[H4]; "queen check")]
+    #[test_case("4r2k/8/8/8/8/5n2/3Q4/4K3 w - - 0 1", &[F3, E8]; "double check")]
+    #[test_case("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1", &[D2]; "pawn check")]
+    #[test_case("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", &[]; "not in check")]
+    fn finds_checkers(fen: &str, expected: &[Square]) {
+        let board = fen.parse::<AnyBoard>().unwrap();
+        assert_eq!(squares(board.checkers()).collect::<Vec<_>>(), expected);
+        assert_eq!(board.in_check(), !expected.is_empty());
+    }
+
+    #[test]
+    fn finds_pinned_pieces() {
+        let board = "4r2k/8/8/8/1b6/8/3NR3/4K3 w - - 0 1"
+            .parse::<AnyBoard>()
+            .unwrap();
+        assert_eq!(board.pinned(), D2.to_bit() | E2.to_bit());
+        // An enemy piece in the way pins nothing
+        let board = "4r2k/8/8/8/1b6/2p5/3N4/4K3 w - - 0 1"
+            .parse::<AnyBoard>()
+            .unwrap();
+        assert_eq!(board.pinned(), 0);
+    }
+
+    #[test_case("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3" => Some(Termination::Checkmate); "fools mate")]
+    #[test_case("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1" => Some(Termination::Stalemate); "stalemate")]
+    #[test_case("4r2k/8/8/8/8/5n2/3Q4/4K3 w - - 0 1" => None; "check with an escape")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1" => None; "starting position")]
+    fn classifies_terminations(fen: &str) -> Option<Termination> {
+        fen.parse::<AnyBoard>().unwrap().termination()
+    }
+}
@@ -0,0 +1,74 @@
+use crate::castle_direction::CastlingRooks;
+use crate::chess_move::{Move, MoveKind, UciMove};
+use crate::square::Square;
+
+use core::ops::Deref;
+
+/// More than the most legal moves any reachable position has (218)
+pub const MAX_MOVES: usize = 256;
+
+/// A fixed capacity stack allocated list of moves
+#[derive(Copy, Clone, Debug)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub const fn new() -> Self {
+        Self {
+            moves: [Move::new(Square::A1, Square::A1, MoveKind::Quiet); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, mv: Move) {
+        debug_assert!(self.len < MAX_MOVES);
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    pub fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+
+    /// The move in this list written as `uci`, if any
+    pub fn find_uci(&self, uci: UciMove) -> Option<Move> {
+        self.iter().copied().find(|mv| mv.to_uci() == uci)
+    }
+
+    /// The move in this list written as `uci` in Chess960 notation, see
+    /// [`Move::to_uci_chess960`]
+    pub fn find_uci_chess960(&self, uci: UciMove, castling_rooks: CastlingRooks) -> Option<Move> {
+        self.iter()
+            .copied()
+            .find(|mv| mv.to_uci_chess960(castling_rooks) == uci)
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = core::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
@@ -0,0 +1,400 @@
+use crate::piece_arrangement::PieceArrangement;
+use crate::piece_type::PieceType;
+use crate::player::Player;
+use crate::square::{Square, SQUARES};
+
+use core::fmt::{self, Debug, Display, Formatter};
+use std::io;
+use std::path::Path;
+use std::simd::cmp::SimdOrd;
+use std::simd::num::SimdInt;
+use std::simd::{i16x16, i32x16, Simd};
+use std::sync::{Mutex, PoisonError};
+
+/// One input per piece type and colour on each square, as seen from one side
+pub const INPUTS: usize = 2 * PieceType::COUNT * Square::COUNT;
+/// Neurons in each side's half of the accumulator
+pub const HIDDEN: usize = 128;
+/// The accumulator is clipped to `0..=QA` before the output layer
+const QA: i32 = 255;
+/// The output weights are quantised by this factor
+const QB: i32 = 64;
+/// Converts the network's output to centipawns
+const SCALE: i64 = 400;
+/// Keeps even a badly trained network's output clear of mate scores
+const MAX_EVALUATION: i64 = 10_000;
+/// The size of a network file: the feature weights and biases, the output weights for the side to
+/// move then the other side, then the output bias, all little-endian `i16`
+pub const NETWORK_BYTES: usize = 2 * (INPUTS * HIDDEN + HIDDEN + 2 * HIDDEN + 1);
+
+/// Why a network file could not be loaded
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum NetworkError {
+    Io { path: String, kind: io::ErrorKind },
+    WrongSize { expected: usize, found: usize },
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Io { path, kind } => write!(f, "cannot read network '{path}': {kind}"),
+            NetworkError::WrongSize { expected, found } => {
+                write!(f, "network is {found} bytes, expected {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// The weights of a `768 -> 128x2 -> 1` perspective network with a clipped ReLU
+#[derive(Clone, Eq, PartialEq)]
+pub struct Network {
+    feature_weights: [[i16; HIDDEN]; INPUTS],
+    feature_bias: [i16; HIDDEN],
+    /// The weights for the side to move's half of the accumulator, then the other side's
+    output_weights: [[i16; HIDDEN]; Player::COUNT],
+    output_bias: i16,
+}
+
+impl Debug for Network {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Network").finish_non_exhaustive()
+    }
+}
+
+impl Network {
+    /// Read a network laid out as described by [`NETWORK_BYTES`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Box<Self>, NetworkError> {
+        if bytes.len() != NETWORK_BYTES {
+            return Err(NetworkError::WrongSize {
+                expected: NETWORK_BYTES,
+                found: bytes.len(),
+            });
+        }
+
+        let mut values = bytes
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]));
+        let mut network = Box::new(Self {
+            feature_weights: [[0; HIDDEN]; INPUTS],
+            feature_bias: [0; HIDDEN],
+            output_weights: [[0; HIDDEN]; Player::COUNT],
+            output_bias: 0,
+        });
+        let weights = network
+            .feature_weights
+            .iter_mut()
+            .flatten()
+            .chain(&mut network.feature_bias)
+            .chain(network.output_weights.iter_mut().flatten())
+            .chain([&mut network.output_bias]);
+        for (weight, value) in weights.zip(&mut values) {
+            *weight = value;
+        }
+
+        Ok(network)
+    }
+
+    /// Load the network file at `path` for the rest of the process, so boards can refer to it
+    ///
+    /// Loading weights that were loaded before, as a GUI does by sending `EvalFile` again, hands
+    /// back the earlier network instead of keeping another copy.
+    pub fn load(path: impl AsRef<Path>) -> Result<&'static Self, NetworkError> {
+        static LOADED: Mutex<Vec<&'static Network>> = Mutex::new(Vec::new());
+
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|error| NetworkError::Io {
+            path: path.display().to_string(),
+            kind: error.kind(),
+        })?;
+        let network = Self::from_bytes(&bytes)?;
+        let mut loaded = LOADED.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(&known) = loaded.iter().find(|&&known| *known == *network) {
+            return Ok(known);
+        }
+        let network = Box::leak(network);
+        loaded.push(network);
+
+        Ok(network)
+    }
+
+    /// The network's evaluation in centipawns from the point of view of `side_to_move`
+    pub fn evaluate(&self, accumulator: &Accumulator, side_to_move: Player) -> i32 {
+        let ours = &accumulator.0[side_to_move as usize];
+        let theirs = &accumulator.0[side_to_move.switch() as usize];
+        let output = dense(ours, &self.output_weights[0]) as i64
+            + dense(theirs, &self.output_weights[1]) as i64
+            + self.output_bias as i64;
+
+        (output * SCALE / (QA * QB) as i64).clamp(-MAX_EVALUATION, MAX_EVALUATION) as i32
+    }
+}
+
+/// The input for `player`'s `piece` on `square`, as seen from `perspective`'s side of the board
+const fn feature(perspective: Player, player: Player, piece: PieceType, square: Square) -> usize {
+    let (side, square) = match perspective {
+        Player::White => (player as usize, square as usize),
+        Player::Black => (player.switch() as usize, square as usize ^ 56),
+    };
+    (side * PieceType::COUNT + piece as usize) * Square::COUNT + square
+}
+
+/// The first layer's output from each side's perspective, indexed by [`Player`], kept up to date
+/// as pieces come and go instead of recomputed for every evaluation
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct Accumulator([[i16; HIDDEN]; Player::COUNT]);
+
+impl Debug for Accumulator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Accumulator").finish_non_exhaustive()
+    }
+}
+
+impl Accumulator {
+    /// The accumulator of boards without a network
+    pub const EMPTY: Self = Self([[0; HIDDEN]; Player::COUNT]);
+
+    /// The accumulator for `pieces` computed from scratch
+    pub fn new(network: &Network, pieces: &PieceArrangement) -> Self {
+        let mut accumulator = Self([network.feature_bias; Player::COUNT]);
+        for square in SQUARES {
+            if let (Some(player), Some(piece)) =
+                (pieces.player_on(square), pieces.piece_type_on(square))
+            {
+                accumulator.add(network, player, piece, square);
+            }
+        }
+        accumulator
+    }
+
+    const fn update(
+        &mut self,
+        network: &Network,
+        player: Player,
+        piece: PieceType,
+        square: Square,
+        add: bool,
+    ) {
+        let features = [
+            feature(Player::White, player, piece, square),
+            feature(Player::Black, player, piece, square),
+        ];
+        let mut perspective = 0;
+        while perspective < Player::COUNT {
+            let weights = &network.feature_weights[features[perspective]];
+            let mut neuron = 0;
+            while neuron < HIDDEN {
+                let value = &mut self.0[perspective][neuron];
+                *value = if add {
+                    value.wrapping_add(weights[neuron])
+                } else {
+                    value.wrapping_sub(weights[neuron])
+                };
+                neuron += 1;
+            }
+            perspective += 1;
+        }
+    }
+
+    pub const fn add(
+        &mut self,
+        network: &Network,
+        player: Player,
+        piece: PieceType,
+        square: Square,
+    ) {
+        self.update(network, player, piece, square, true);
+    }
+
+    pub const fn remove(
+        &mut self,
+        network: &Network,
+        player: Player,
+        piece: PieceType,
+        square: Square,
+    ) {
+        self.update(network, player, piece, square, false);
+    }
+}
+
+/// Dot product of the clipped accumulator half with the output weights
+fn dense(accumulator: &[i16; HIDDEN], weights: &[i16; HIDDEN]) -> i32 {
+    if cfg!(any(
+        target_feature = "sse2",
+        target_feature = "neon",
+        target_feature = "simd128"
+    )) {
+        dense_simd(accumulator, weights)
+    } else {
+        dense_scalar(accumulator, weights)
+    }
+}
+
+fn dense_simd(accumulator: &[i16; HIDDEN], weights: &[i16; HIDDEN]) -> i32 {
+    let (zero, max) = (i16x16::splat(0), i16x16::splat(QA as i16));
+    let mut sum = i32x16::splat(0);
+    for (values, weights) in accumulator
+        .as_chunks::<16>()
+        .0
+        .iter()
+        .zip(weights.as_chunks::<16>().0)
+    {
+        let clipped: i32x16 = Simd::from_array(*values).simd_clamp(zero, max).cast();
+        sum += clipped * Simd::<i16, 16>::from_array(*weights).cast();
+    }
+    sum.reduce_sum()
+}
+
+fn dense_scalar(accumulator: &[i16; HIDDEN], weights: &[i16; HIDDEN]) -> i32 {
+    accumulator
+        .iter()
+        .zip(weights)
+        .map(|(&value, &weight)| (value as i32).clamp(0, QA) * weight as i32)
+        .sum()
+}
+
+/// The NNUE evaluation of `pieces` from the point of view of `side_to_move`, `None` unless a
+/// network has been attached with [`PieceArrangement::with_network`]
+pub fn evaluate(pieces: &PieceArrangement, side_to_move: Player) -> Option<i32> {
+    pieces
+        .network()
+        .map(|network| network.evaluate(pieces.accumulator(), side_to_move))
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::any_board::AnyBoard;
+    use crate::nnue::{
+        dense_scalar, dense_simd, Accumulator, Network, NetworkError, HIDDEN, NETWORK_BYTES,
+    };
+    use crate::player::Player;
+    use std::sync::OnceLock;
+
+    /// A network of arbitrary but repeatable weights
+    pub(crate) fn test_network() -> &'static Network {
+        static NETWORK: OnceLock<Box<Network>> = OnceLock::new();
+        NETWORK.get_or_init(|| {
+            let mut state = 0x9e37_79b9_7f4a_7c15u64;
+            let bytes: Vec<u8> = (0..NETWORK_BYTES / 2)
+                .flat_map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    ((state % 101) as i16 - 50).to_le_bytes()
+                })
+                .collect();
+            Network::from_bytes(&bytes).unwrap()
+        })
+    }
+
+    #[test]
+    fn rejects_files_of_the_wrong_size() {
+        assert_eq!(
+            Network::from_bytes(&[0; 10]).unwrap_err(),
+            NetworkError::WrongSize {
+                expected: NETWORK_BYTES,
+                found: 10
+            }
+        );
+        assert!(matches!(
+            Network::load("/nonexistent/net.bin"),
+            Err(NetworkError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn reloading_reuses_the_network() {
+        let path = std::env::temp_dir().join(format!("magnesium-test-{}.nnue", std::process::id()));
+        let mut bytes = vec![0; NETWORK_BYTES];
+        std::fs::write(&path, &bytes).unwrap();
+        let first = Network::load(&path).unwrap();
+        let second = Network::load(&path).unwrap();
+        bytes[0] = 1;
+        std::fs::write(&path, &bytes).unwrap();
+        let changed = Network::load(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert!(core::ptr::eq(first, second));
+        assert!(!core::ptr::eq(first, changed));
+    }
+
+    #[test]
+    fn boards_compare_their_network_by_address() {
+        let board = AnyBoard::default();
+        let copy = Box::leak(Box::new(test_network().clone()));
+        assert_eq!(
+            board.with_network(Some(test_network())),
+            board.with_network(Some(test_network()))
+        );
+        assert_ne!(
+            board.with_network(Some(test_network())),
+            board.with_network(Some(copy))
+        );
+        assert_ne!(board.with_network(Some(test_network())), board);
+    }
+
+    #[test]
+    fn simd_matches_scalar() {
+        let network = test_network();
+        let mut accumulator = [0; HIDDEN];
+        for (neuron, value) in accumulator.iter_mut().enumerate() {
+            *value = (neuron as i16 * 37) % 600 - 200;
+        }
+        for weights in &network.output_weights {
+            assert_eq!(
+                dense_simd(&accumulator, weights),
+                dense_scalar(&accumulator, weights)
+            );
+        }
+    }
+
+    /// Walk every line `depth` moves deep, checking the incrementally updated accumulators
+    fn check_accumulators(board: AnyBoard, depth: u32) {
+        let pieces = board.pieces();
+        assert_eq!(
+            *pieces.accumulator(),
+            Accumulator::new(test_network(), pieces),
+            "{board:?}"
+        );
+        if depth > 0 {
+            for &mv in board.legal_moves().iter() {
+                check_accumulators(board.make_move(mv), depth - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn accumulators_update_incrementally() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        ] {
+            let board = fen
+                .parse::<AnyBoard>()
+                .unwrap()
+                .with_network(Some(test_network()));
+            check_accumulators(board, 2);
+        }
+    }
+
+    #[test]
+    fn evaluates_both_sides_alike() {
+        let white = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+            .parse::<AnyBoard>()
+            .unwrap()
+            .with_network(Some(test_network()));
+        let black = "rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3"
+            .parse::<AnyBoard>()
+            .unwrap()
+            .with_network(Some(test_network()));
+        let score =
+            |board: AnyBoard| crate::nnue::evaluate(board.pieces(), board.side_to_move()).unwrap();
+        assert_eq!(score(white), score(black));
+        assert_eq!(
+            crate::nnue::evaluate(AnyBoard::default().pieces(), Player::White),
+            None
+        );
+    }
+}
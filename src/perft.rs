@@ -0,0 +1,149 @@
+use crate::any_board::AnyBoard;
+use crate::chess_move::Move;
+
+/// Count the leaves of the legal move tree `depth` plies below `board`
+///
+/// The last ply is counted from the move list without playing the moves.
+pub fn perft(board: AnyBoard, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = board.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves
+        .iter()
+        .map(|&mv| perft(board.make_move(mv), depth - 1))
+        .sum()
+}
+
+/// [`perft`] split by root move, for finding which subtree disagrees with another engine
+pub fn divide(board: AnyBoard, depth: u32) -> Vec<(Move, u64)> {
+    board
+        .legal_moves()
+        .iter()
+        .map(|&mv| (mv, perft(board.make_move(mv), depth.saturating_sub(1))))
+        .collect()
+}
+
+/// Print [`divide`] in the format most engines use, `e2e4: 9771` per root move followed by the
+/// total, and return the total
+///
+/// With `chess960` castles are written as the king taking its own rook.
+pub fn print_divide(board: AnyBoard, depth: u32, chess960: bool) -> u64 {
+    let counts = divide(board, depth);
+    for (mv, nodes) in &counts {
+        if chess960 {
+            println!("{}: {nodes}", mv.to_uci_chess960(board.castling_rooks()));
+        } else {
+            println!("{mv}: {nodes}");
+        }
+    }
+    let total = counts.iter().map(|(_, nodes)| nodes).sum();
+    println!("\nNodes searched: {total}");
+
+    total
+}
+
+#[cfg(test)]
+mod test {
+    use crate::any_board::AnyBoard;
+    use crate::fen::STARTING_FEN;
+    use crate::perft::{divide, perft};
+    use test_case::test_case;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_4_MIRRORED: &str =
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    // The standard positions from the chess programming wiki
+    #[test_case(STARTING_FEN, 1 => 20)]
+    #[test_case(STARTING_FEN, 2 => 400)]
+    #[test_case(STARTING_FEN, 3 => 8_902)]
+    #[test_case(STARTING_FEN, 4 => 197_281)]
+    #[test_case(STARTING_FEN, 5 => ignore 4_865_609)]
+    #[test_case(STARTING_FEN, 6 => ignore 119_060_324)]
+    #[test_case(KIWIPETE, 1 => 48)]
+    #[test_case(KIWIPETE, 2 => 2_039)]
+    #[test_case(KIWIPETE, 3 => 97_862)]
+    #[test_case(KIWIPETE, 4 => ignore 4_085_603)]
+    #[test_case(KIWIPETE, 5 => ignore 193_690_690)]
+    #[test_case(POSITION_3, 1 => 14)]
+    #[test_case(POSITION_3, 2 => 191)]
+    #[test_case(POSITION_3, 3 => 2_812)]
+    #[test_case(POSITION_3, 4 => 43_238)]
+    #[test_case(POSITION_3, 5 => 674_624)]
+    #[test_case(POSITION_3, 6 => ignore 11_030_083)]
+    #[test_case(POSITION_4, 1 => 6)]
+    #[test_case(POSITION_4, 2 => 264)]
+    #[test_case(POSITION_4, 3 => 9_467)]
+    #[test_case(POSITION_4, 4 => 422_333)]
+    #[test_case(POSITION_4, 5 => ignore 15_833_292)]
+    #[test_case(POSITION_4_MIRRORED, 4 => 422_333)]
+    #[test_case(POSITION_4_MIRRORED, 5 => ignore 15_833_292)]
+    #[test_case(POSITION_5, 1 => 44)]
+    #[test_case(POSITION_5, 2 => 1_486)]
+    #[test_case(POSITION_5, 3 => 62_379)]
+    #[test_case(POSITION_5, 4 => ignore 2_103_487)]
+    #[test_case(POSITION_5, 5 => ignore 89_941_194)]
+    #[test_case(POSITION_6, 1 => 46)]
+    #[test_case(POSITION_6, 2 => 2_079)]
+    #[test_case(POSITION_6, 3 => 89_890)]
+    #[test_case(POSITION_6, 4 => ignore 3_894_594)]
+    #[test_case(POSITION_6, 5 => ignore 164_075_551)]
+    fn standard_positions(fen: &str, depth: u32) -> u64 {
+        perft(fen.parse().unwrap(), depth)
+    }
+
+    #[test_case("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 6 => ignore 1_134_888; "en passant exposes king horizontally")]
+    #[test_case("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 4 => 10_138; "en passant exposes king horizontally shallow")]
+    #[test_case("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", 6 => ignore 1_015_133; "en passant exposes king diagonally")]
+    #[test_case("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 6 => ignore 1_440_467; "en passant capture gives check")]
+    #[test_case("5k2/8/8/8/8/8/8/4K2R w K - 0 1", 6 => ignore 661_072; "short castle gives check")]
+    #[test_case("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", 6 => ignore 803_711; "long castle gives check")]
+    #[test_case("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 4 => ignore 1_274_206; "castling rights lost")]
+    #[test_case("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 4 => ignore 1_720_476; "castling prevented")]
+    #[test_case("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 6 => ignore 3_821_001; "promote out of check")]
+    #[test_case("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", 5 => ignore 1_004_658; "discovered check")]
+    #[test_case("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", 6 => 217_342; "promote to give check")]
+    #[test_case("8/P1k5/K7/8/8/8/8/8 w - - 0 1", 6 => 92_683; "underpromote to check")]
+    #[test_case("K1k5/8/P7/8/8/8/8/8 w - - 0 1", 6 => 2_217; "self stalemate")]
+    #[test_case("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", 7 => ignore 567_584; "stalemate and checkmate")]
+    #[test_case("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", 4 => 23_527; "checkmate and stalemate")]
+    fn edge_cases(fen: &str, depth: u32) -> u64 {
+        perft(fen.parse().unwrap(), depth)
+    }
+
+    // Chess960 positions from the chess programming wiki, with castling rights in Shredder-FEN
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", 4 => 326_672; "position 1")]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", 5 => ignore 8_146_062; "position 1 deep")]
+    #[test_case("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", 4 => 667_366; "position 2")]
+    #[test_case("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", 5 => ignore 16_253_601; "position 2 deep")]
+    #[test_case("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", 4 => 273_318; "position 3")]
+    #[test_case("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", 5 => ignore 6_417_013; "position 3 deep")]
+    fn chess960_positions(fen: &str, depth: u32) -> u64 {
+        perft(fen.parse().unwrap(), depth)
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let board: AnyBoard = KIWIPETE.parse().unwrap();
+        let counts = divide(board, 3);
+        assert_eq!(counts.len(), 48);
+        assert_eq!(counts.iter().map(|(_, nodes)| nodes).sum::<u64>(), 97_862);
+        let castle = counts.iter().find(|(mv, _)| mv.to_string() == "e1g1");
+        assert_eq!(castle.map(|&(_, nodes)| nodes), Some(2_059));
+    }
+
+    #[test]
+    fn depth_zero_is_the_position_itself() {
+        assert_eq!(perft(AnyBoard::default(), 0), 1);
+    }
+}
@@ -1,21 +1,131 @@
+use crate::attacks::{
+    bishop_attacks, king_attacks, knight_attacks, knight_attacks_mask, pawn_attacks,
+    pawn_attacks_mask, queen_attacks, rook_attacks,
+};
 use crate::bitboard::{Bitboard, EMPTY_BITBOARD};
+use crate::nnue::{Accumulator, Network};
 use crate::piece_type::{NonKingPieceType, PieceType};
 use crate::player::Player;
-use crate::square::Square;
+use crate::square::{Square, SQUARES};
+use crate::zobrist::piece_key;
 
+use core::fmt::{self, Display, Formatter};
+
+const fn player(is_white: bool) -> Player {
+    if is_white {
+        Player::White
+    } else {
+        Player::Black
+    }
+}
+
+/// Why a [`PieceArrangement`] refused a change that would break it
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ArrangementError {
+    /// No piece stands on the square a piece should move or be removed from
+    EmptySquare(Square),
+    /// The piece on the square belongs to the other side
+    WrongPlayer(Square),
+    /// The piece on the square is not the one the change is for
+    WrongPieceType(Square),
+    /// The square a piece should move to or be added on is taken
+    Occupied(Square),
+    /// Kings only ever move, they are never removed
+    King(Square),
+    /// The piece on the square cannot make the move the change is for
+    Unreachable(Square),
+}
+
+impl Display for ArrangementError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ArrangementError::EmptySquare(square) => write!(f, "no piece on {square}"),
+            ArrangementError::WrongPlayer(square) => {
+                write!(f, "the piece on {square} belongs to the other side")
+            }
+            ArrangementError::WrongPieceType(square) => {
+                write!(f, "the piece on {square} is not the expected one")
+            }
+            ArrangementError::Occupied(square) => write!(f, "{square} is already occupied"),
+            ArrangementError::King(square) => write!(f, "the king on {square} cannot be removed"),
+            ArrangementError::Unreachable(square) => {
+                write!(f, "the piece on {square} cannot make that move")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArrangementError {}
+
+#[derive(Copy, Clone, Debug)]
 pub struct PieceArrangement {
     king_squares: [Square; Player::COUNT],
     occupied_by_player: [Bitboard; Player::COUNT],
     occupied_by_piece: [Bitboard; NonKingPieceType::COUNT],
     piece_by_square: [Option<PieceType>; Square::COUNT],
+    /// The Zobrist key of every piece on the board, kept up to date by each change
+    zobrist: u64,
+    /// The network evaluating this arrangement, if any, whose accumulator is kept up to date by
+    /// each change like the Zobrist key
+    network: Option<&'static Network>,
+    accumulator: Accumulator,
 }
 
+/// The accumulator follows from the pieces and the network, which is compared by address rather
+/// than weight by weight
+impl PartialEq for PieceArrangement {
+    fn eq(&self, other: &Self) -> bool {
+        let same_network = match (self.network, other.network) {
+            (Some(network), Some(other)) => core::ptr::eq(network, other),
+            (network, other) => network.is_none() && other.is_none(),
+        };
+        self.king_squares == other.king_squares
+            && self.occupied_by_player == other.occupied_by_player
+            && self.occupied_by_piece == other.occupied_by_piece
+            && self.piece_by_square == other.piece_by_square
+            && self.zobrist == other.zobrist
+            && same_network
+    }
+}
+
+impl Eq for PieceArrangement {}
+
 impl PieceArrangement {
     pub const fn king_square<const IS_WHITE: bool>(&self) -> Square {
         self.king_squares[IS_WHITE as usize]
     }
 
+    /// [`PieceArrangement::king_square`] for a player only known at runtime
+    pub const fn king_square_for_side(&self, player: Player) -> Square {
+        self.king_squares[player.is_white() as usize]
+    }
+
+    /// The part of a position's Zobrist key that covers piece placement
+    pub const fn zobrist(&self) -> u64 {
+        self.zobrist
+    }
+
+    pub const fn network(&self) -> Option<&'static Network> {
+        self.network
+    }
+
+    pub const fn accumulator(&self) -> &Accumulator {
+        &self.accumulator
+    }
+
+    /// Evaluate this arrangement with `network` from now on, or stop using one with `None`,
+    /// computing the accumulator from scratch
+    pub fn with_network(self, network: Option<&'static Network>) -> Self {
+        Self {
+            network,
+            accumulator: match network {
+                Some(network) => Accumulator::new(network, &self),
+                None => Accumulator::EMPTY,
+            },
+            ..self
+        }
+    }
+
     pub const fn piece_type_on(&self, square: Square) -> Option<PieceType> {
         self.piece_by_square[square as usize]
     }
@@ -40,6 +150,16 @@ impl PieceArrangement {
         self.occupied_by_piece[PIECE_TYPE as usize]
     }
 
+    /// [`PieceArrangement::mask_for_piece`] for a piece type only known at runtime
+    pub const fn mask_for_piece_type(&self, piece: NonKingPieceType) -> Bitboard {
+        self.occupied_by_piece[piece as usize]
+    }
+
+    /// [`PieceArrangement::mask_for_player`] for a player only known at runtime
+    pub const fn mask_for_side(&self, player: Player) -> Bitboard {
+        self.occupied_by_player[player.is_white() as usize]
+    }
+
     pub const fn mask_for_player<const IS_WHITE: bool>(&self) -> Bitboard {
         self.occupied_by_player[IS_WHITE as usize]
     }
@@ -53,6 +173,79 @@ impl PieceArrangement {
         self.mask_for_player::<{ IS_WHITE }>() & self.mask_for_piece::<PIECE_TYPE>()
     }
 
+    /// The squares of both kings
+    pub const fn kings(&self) -> Bitboard {
+        self.king_squares[Player::White as usize].to_bit()
+            | self.king_squares[Player::Black as usize].to_bit()
+    }
+
+    /// The pieces of the opponent of the given side
+    pub const fn mask_for_opponent<const IS_WHITE: bool>(&self) -> Bitboard {
+        self.occupied_by_player[!IS_WHITE as usize]
+    }
+
+    pub const fn mask_for_opponent_and_piece<
+        const IS_WHITE: bool,
+        const PIECE_TYPE: NonKingPieceType,
+    >(
+        &self,
+    ) -> Bitboard {
+        self.mask_for_opponent::<{ IS_WHITE }>() & self.mask_for_piece::<PIECE_TYPE>()
+    }
+
+    /// Pieces of either side attacking `square`, with sliders blocked by `occupied`
+    pub const fn attackers_to(&self, square: Square, occupied: Bitboard) -> Bitboard {
+        let pawns = self.mask_for_piece::<{ NonKingPieceType::Pawn }>();
+        let queens = self.mask_for_piece::<{ NonKingPieceType::Queen }>();
+        let diagonal = self.mask_for_piece::<{ NonKingPieceType::Bishop }>() | queens;
+        let orthogonal = self.mask_for_piece::<{ NonKingPieceType::Rook }>() | queens;
+
+        (pawn_attacks::<true>(square) & pawns & self.mask_for_player::<false>())
+            | (pawn_attacks::<false>(square) & pawns & self.mask_for_player::<true>())
+            | (knight_attacks(square) & self.mask_for_piece::<{ NonKingPieceType::Knight }>())
+            | (king_attacks(square) & self.kings())
+            | (bishop_attacks(square, occupied) & diagonal)
+            | (rook_attacks(square, occupied) & orthogonal)
+    }
+
+    /// Every square attacked by the given side's pieces, including squares occupied by its own pieces
+    pub const fn attacked_by<const IS_WHITE: bool>(&self) -> Bitboard {
+        self.attacked_by_with_occupancy::<{ IS_WHITE }>(self.occupied())
+    }
+
+    /// Every square attacked by the given side's pieces, with sliders blocked by `occupied`
+    pub const fn attacked_by_with_occupancy<const IS_WHITE: bool>(
+        &self,
+        occupied: Bitboard,
+    ) -> Bitboard {
+        let mut attacked = pawn_attacks_mask::<{ IS_WHITE }>(
+            self.mask_for_player_and_piece::<{ IS_WHITE }, { NonKingPieceType::Pawn }>(),
+        ) | knight_attacks_mask(
+            self.mask_for_player_and_piece::<{ IS_WHITE }, { NonKingPieceType::Knight }>(),
+        ) | king_attacks(self.king_square::<{ IS_WHITE }>());
+
+        let mut bishops =
+            self.mask_for_player_and_piece::<{ IS_WHITE }, { NonKingPieceType::Bishop }>();
+        while bishops != EMPTY_BITBOARD {
+            attacked |= bishop_attacks(SQUARES[bishops.trailing_zeros() as usize], occupied);
+            bishops &= bishops - 1;
+        }
+        let mut rooks =
+            self.mask_for_player_and_piece::<{ IS_WHITE }, { NonKingPieceType::Rook }>();
+        while rooks != EMPTY_BITBOARD {
+            attacked |= rook_attacks(SQUARES[rooks.trailing_zeros() as usize], occupied);
+            rooks &= rooks - 1;
+        }
+        let mut queens =
+            self.mask_for_player_and_piece::<{ IS_WHITE }, { NonKingPieceType::Queen }>();
+        while queens != EMPTY_BITBOARD {
+            attacked |= queen_attacks(SQUARES[queens.trailing_zeros() as usize], occupied);
+            queens &= queens - 1;
+        }
+
+        attacked
+    }
+
     pub const fn new(white_king: Square, black_king: Square) -> Self {
         let king_squares = [black_king, white_king];
         let occupied_by_player = [black_king.to_bit(), white_king.to_bit()];
@@ -68,6 +261,10 @@ impl PieceArrangement {
             occupied_by_player,
             piece_by_square,
             occupied_by_piece: [EMPTY_BITBOARD; NonKingPieceType::COUNT],
+            zobrist: piece_key(Player::White, PieceType::King, white_king)
+                ^ piece_key(Player::Black, PieceType::King, black_king),
+            network: None,
+            accumulator: Accumulator::EMPTY,
         }
     }
 
@@ -80,19 +277,68 @@ impl PieceArrangement {
             mut occupied_by_piece,
             mut piece_by_square,
             king_squares: _,
+            zobrist,
+            network,
+            mut accumulator,
         } = self;
         debug_assert!(self.occupied() & square.to_bit() == EMPTY_BITBOARD);
         occupied_by_player[IS_WHITE as usize] |= square.to_bit();
         occupied_by_piece[PIECE as usize] |= square.to_bit();
         piece_by_square[square as usize] = Some(PIECE.to_piece_type());
+        if let Some(network) = network {
+            accumulator.add(network, player(IS_WHITE), PIECE.to_piece_type(), square);
+        }
         Self {
             occupied_by_player,
             occupied_by_piece,
             piece_by_square,
+            zobrist: zobrist ^ piece_key(player(IS_WHITE), PIECE.to_piece_type(), square),
+            accumulator,
             ..self
         }
     }
 
+    /// [`PieceArrangement::add_piece`] for a player and piece only known at runtime
+    pub const fn add_piece_for(
+        self,
+        player: Player,
+        piece: NonKingPieceType,
+        square: Square,
+    ) -> Self {
+        match (player.is_white(), piece) {
+            (true, NonKingPieceType::Pawn) => {
+                self.add_piece::<true, { NonKingPieceType::Pawn }>(square)
+            }
+            (true, NonKingPieceType::Knight) => {
+                self.add_piece::<true, { NonKingPieceType::Knight }>(square)
+            }
+            (true, NonKingPieceType::Bishop) => {
+                self.add_piece::<true, { NonKingPieceType::Bishop }>(square)
+            }
+            (true, NonKingPieceType::Rook) => {
+                self.add_piece::<true, { NonKingPieceType::Rook }>(square)
+            }
+            (true, NonKingPieceType::Queen) => {
+                self.add_piece::<true, { NonKingPieceType::Queen }>(square)
+            }
+            (false, NonKingPieceType::Pawn) => {
+                self.add_piece::<false, { NonKingPieceType::Pawn }>(square)
+            }
+            (false, NonKingPieceType::Knight) => {
+                self.add_piece::<false, { NonKingPieceType::Knight }>(square)
+            }
+            (false, NonKingPieceType::Bishop) => {
+                self.add_piece::<false, { NonKingPieceType::Bishop }>(square)
+            }
+            (false, NonKingPieceType::Rook) => {
+                self.add_piece::<false, { NonKingPieceType::Rook }>(square)
+            }
+            (false, NonKingPieceType::Queen) => {
+                self.add_piece::<false, { NonKingPieceType::Queen }>(square)
+            }
+        }
+    }
+
     pub const fn remove_piece<const IS_WHITE: bool, const PIECE: NonKingPieceType>(
         self,
         square: Square,
@@ -102,17 +348,25 @@ impl PieceArrangement {
             mut occupied_by_piece,
             mut piece_by_square,
             king_squares: _,
+            zobrist,
+            network,
+            mut accumulator,
         } = self;
         debug_assert!(occupied_by_piece[PIECE as usize] & square.to_bit() != EMPTY_BITBOARD);
         debug_assert!(occupied_by_player[IS_WHITE as usize] & square.to_bit() != EMPTY_BITBOARD);
         occupied_by_player[IS_WHITE as usize] &= !square.to_bit();
         occupied_by_piece[PIECE as usize] &= !square.to_bit();
         piece_by_square[square as usize] = None;
+        if let Some(network) = network {
+            accumulator.remove(network, player(IS_WHITE), PIECE.to_piece_type(), square);
+        }
 
         Self {
             occupied_by_player,
             occupied_by_piece,
             piece_by_square,
+            zobrist: zobrist ^ piece_key(player(IS_WHITE), PIECE.to_piece_type(), square),
+            accumulator,
             ..self
         }
     }
@@ -127,6 +381,9 @@ impl PieceArrangement {
             mut occupied_by_piece,
             mut piece_by_square,
             mut king_squares,
+            zobrist,
+            network,
+            mut accumulator,
         } = self;
         debug_assert!(self.occupied() & to.to_bit() == EMPTY_BITBOARD);
         let from_to = from.to_bit() | to.to_bit();
@@ -141,13 +398,20 @@ impl PieceArrangement {
         occupied_by_player[IS_WHITE as usize] ^= from_to;
         piece_by_square[from as usize] = None;
         piece_by_square[to as usize] = Some(PIECE);
+        let player = player(IS_WHITE);
+        if let Some(network) = network {
+            accumulator.remove(network, player, PIECE, from);
+            accumulator.add(network, player, PIECE, to);
+        }
 
         Self {
             occupied_by_player,
             occupied_by_piece,
             piece_by_square,
             king_squares,
-            ..self
+            zobrist: zobrist ^ piece_key(player, PIECE, from) ^ piece_key(player, PIECE, to),
+            network,
+            accumulator,
         }
     }
 
@@ -190,10 +454,129 @@ impl PieceArrangement {
             None => panic!("attepting to move from empty square"),
         }
     }
+
+    /// The type of the given side's piece on `square`, failing when it is empty or the other
+    /// side's
+    pub const fn own_piece_on<const IS_WHITE: bool>(
+        &self,
+        square: Square,
+    ) -> Result<PieceType, ArrangementError> {
+        match (self.piece_type_on(square), self.player_on(square)) {
+            (Some(piece), Some(player)) if player.is_white() == IS_WHITE => Ok(piece),
+            (Some(_), Some(_)) => Err(ArrangementError::WrongPlayer(square)),
+            _ => Err(ArrangementError::EmptySquare(square)),
+        }
+    }
+
+    /// Whether [`PieceArrangement::add_piece`] may place a piece on `square`
+    pub const fn check_add(&self, square: Square) -> Result<(), ArrangementError> {
+        if self.occupied() & square.to_bit() == EMPTY_BITBOARD {
+            Ok(())
+        } else {
+            Err(ArrangementError::Occupied(square))
+        }
+    }
+
+    /// Whether [`PieceArrangement::move_by_squares`] may move the given side's piece
+    pub const fn check_move<const IS_WHITE: bool>(
+        &self,
+        from: Square,
+        to: Square,
+    ) -> Result<(), ArrangementError> {
+        match self.own_piece_on::<{ IS_WHITE }>(from) {
+            Ok(_) => self.check_add(to),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Whether [`PieceArrangement::remove_by_square`] may remove the given side's piece
+    pub const fn check_remove<const IS_WHITE: bool>(
+        &self,
+        square: Square,
+    ) -> Result<(), ArrangementError> {
+        match self.own_piece_on::<{ IS_WHITE }>(square) {
+            Ok(PieceType::King) => Err(ArrangementError::King(square)),
+            Ok(_) => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`PieceArrangement::add_piece`] that fails instead of corrupting the arrangement when
+    /// `square` is taken
+    pub const fn try_add_piece<const IS_WHITE: bool, const PIECE: NonKingPieceType>(
+        self,
+        square: Square,
+    ) -> Result<Self, ArrangementError> {
+        match self.check_add(square) {
+            Ok(()) => Ok(self.add_piece::<{ IS_WHITE }, { PIECE }>(square)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`PieceArrangement::move_by_squares`] that fails instead of panicking when `from` does not
+    /// hold the given side's piece or `to` is taken
+    pub const fn try_move_by_squares<const IS_WHITE: bool>(
+        self,
+        from: Square,
+        to: Square,
+    ) -> Result<Self, ArrangementError> {
+        match self.check_move::<{ IS_WHITE }>(from, to) {
+            Ok(()) => Ok(self.move_by_squares::<{ IS_WHITE }>(from, to)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// [`PieceArrangement::remove_by_square`] that fails instead of panicking when `square` does
+    /// not hold one of the given side's pieces other than its king
+    pub const fn try_remove_by_square<const IS_WHITE: bool>(
+        self,
+        square: Square,
+    ) -> Result<Self, ArrangementError> {
+        match self.check_remove::<{ IS_WHITE }>(square) {
+            Ok(()) => Ok(self.remove_by_square::<{ IS_WHITE }>(square)),
+            Err(error) => Err(error),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::board::DEFAULT_BOARD;
+    use crate::piece_arrangement::{ArrangementError, PieceArrangement};
+    use crate::piece_type::{NonKingPieceType, PieceType};
+    use crate::square::{Square, Square::*};
+    use test_case::test_case;
+
+    const PIECES: PieceArrangement = *DEFAULT_BOARD.pieces();
+
+    #[test_case(E2, E4 => Ok(()); "own pawn to an empty square")]
+    #[test_case(E4, E5 => Err(ArrangementError::EmptySquare(E4)); "from an empty square")]
+    #[test_case(E7, E5 => Err(ArrangementError::WrongPlayer(E7)); "the other side's pawn")]
+    #[test_case(D1, D2 => Err(ArrangementError::Occupied(D2)); "onto our own pawn")]
+    #[test_case(A1, A8 => Err(ArrangementError::Occupied(A8)); "onto the other side's rook")]
+    fn try_move_by_squares(from: Square, to: Square) -> Result<(), ArrangementError> {
+        PIECES
+            .try_move_by_squares::<true>(from, to)
+            .map(|pieces| assert_eq!(pieces, PIECES.move_by_squares::<true>(from, to)))
+    }
 
-    // TODO
+    #[test_case(G8 => Ok(()); "knight")]
+    #[test_case(E8 => Err(ArrangementError::King(E8)); "king")]
+    #[test_case(E5 => Err(ArrangementError::EmptySquare(E5)); "empty square")]
+    #[test_case(G1 => Err(ArrangementError::WrongPlayer(G1)); "the other side's knight")]
+    fn try_remove_by_square(square: Square) -> Result<(), ArrangementError> {
+        PIECES
+            .try_remove_by_square::<false>(square)
+            .map(|pieces| assert_eq!(pieces.piece_type_on(square), None))
+    }
+
+    #[test]
+    fn try_add_piece() {
+        let pieces = PIECES.try_add_piece::<true, { NonKingPieceType::Queen }>(D4);
+        assert_eq!(pieces.unwrap().piece_type_on(D4), Some(PieceType::Queen));
+        assert_eq!(
+            PIECES.try_add_piece::<true, { NonKingPieceType::Queen }>(D8),
+            Err(ArrangementError::Occupied(D8))
+        );
+    }
 }
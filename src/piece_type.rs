@@ -30,6 +30,20 @@ pub enum PromotionPieceType {
     Queen,
 }
 
+impl PieceType {
+    pub const COUNT: usize = 6;
+    pub const fn to_non_king_piece_type(self) -> Option<NonKingPieceType> {
+        match self {
+            PieceType::Pawn => Some(NonKingPieceType::Pawn),
+            PieceType::Knight => Some(NonKingPieceType::Knight),
+            PieceType::Bishop => Some(NonKingPieceType::Bishop),
+            PieceType::Rook => Some(NonKingPieceType::Rook),
+            PieceType::Queen => Some(NonKingPieceType::Queen),
+            PieceType::King => None,
+        }
+    }
+}
+
 impl NonKingPieceType {
     pub const COUNT: usize = 5;
     pub const fn to_piece_type(self) -> PieceType {
@@ -42,3 +56,24 @@ impl NonKingPieceType {
         }
     }
 }
+
+impl PromotionPieceType {
+    pub const COUNT: usize = 4;
+    pub const ALL: [PromotionPieceType; PromotionPieceType::COUNT] = [
+        PromotionPieceType::Queen,
+        PromotionPieceType::Rook,
+        PromotionPieceType::Bishop,
+        PromotionPieceType::Knight,
+    ];
+    pub const fn to_non_king_piece_type(self) -> NonKingPieceType {
+        match self {
+            PromotionPieceType::Knight => NonKingPieceType::Knight,
+            PromotionPieceType::Bishop => NonKingPieceType::Bishop,
+            PromotionPieceType::Rook => NonKingPieceType::Rook,
+            PromotionPieceType::Queen => NonKingPieceType::Queen,
+        }
+    }
+    pub const fn to_piece_type(self) -> PieceType {
+        self.to_non_king_piece_type().to_piece_type()
+    }
+}
@@ -0,0 +1,383 @@
+use crate::bitboard::EMPTY_BITBOARD;
+use crate::castle_direction::{CastleDirection, CastlingRooks};
+use crate::fen::Position;
+use crate::piece_arrangement::PieceArrangement;
+use crate::piece_type::PieceType;
+use crate::player::Player;
+use crate::square::{Square, SQUARES};
+
+use core::fmt::{self, Display, Formatter};
+
+/// Why a [`PositionBuilder`] could not build a legal position
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PositionError {
+    /// A side has no king, or more than one
+    KingCount {
+        player: Player,
+        count: usize,
+    },
+    /// A pawn stands on the first or last rank, where it can neither be nor move
+    PawnOnBackRank(Square),
+    /// The side not to move is in check, so the side to move could capture its king
+    OpponentInCheck,
+    /// A castling right whose king is off its back rank, or whose rook is not on its square on
+    /// that side of the king
+    InvalidCastleRights {
+        player: Player,
+        direction: CastleDirection,
+    },
+    /// The en passant square is not empty on the right rank with the pawn that just double pushed
+    /// past it in front, and the square it came from empty
+    InvalidEnPassant(Square),
+    ZeroFullmoveNumber,
+}
+
+const fn player_name(player: Player) -> &'static str {
+    match player {
+        Player::White => "white",
+        Player::Black => "black",
+    }
+}
+
+impl Display for PositionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionError::KingCount { player, count } => write!(
+                f,
+                "{} has {count} kings instead of one",
+                player_name(*player)
+            ),
+            PositionError::PawnOnBackRank(square) => write!(f, "pawn on the back rank at {square}"),
+            PositionError::OpponentInCheck => f.write_str("the side not to move is in check"),
+            PositionError::InvalidCastleRights { player, direction } => write!(
+                f,
+                "{} cannot have {} castling rights with this king and rook",
+                player_name(*player),
+                match direction {
+                    CastleDirection::KingSide => "king side",
+                    CastleDirection::QueenSide => "queen side",
+                }
+            ),
+            PositionError::InvalidEnPassant(square) => {
+                write!(f, "no pawn can be captured en passant on {square}")
+            }
+            PositionError::ZeroFullmoveNumber => f.write_str("the fullmove number starts at 1"),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+/// Set up an arbitrary position piece by piece, checking it is legal only once it is built
+///
+/// Unlike [`PieceArrangement`], which trusts its caller, nothing here can panic. A builder starts
+/// from an empty board with white to move, no castling rights and clocks of `0 1`, or from an
+/// existing position to edit it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PositionBuilder {
+    pieces: [Option<(Player, PieceType)>; 64],
+    side_to_move: Player,
+    /// Indexed by [`Player`] then [`CastleDirection`]
+    castle_rights: [[bool; CastleDirection::COUNT]; Player::COUNT],
+    castling_rooks: CastlingRooks,
+    en_passant: Option<Square>,
+    halfmove_clock: u16,
+    fullmove_number: u16,
+}
+
+impl PositionBuilder {
+    pub const fn new() -> Self {
+        Self {
+            pieces: [None; 64],
+            side_to_move: Player::White,
+            castle_rights: [[false; CastleDirection::COUNT]; Player::COUNT],
+            castling_rooks: CastlingRooks::STANDARD,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        }
+    }
+
+    /// Place `player`'s `piece` on `square`, replacing whatever was there
+    pub const fn with_piece(mut self, square: Square, player: Player, piece: PieceType) -> Self {
+        self.pieces[square as usize] = Some((player, piece));
+        self
+    }
+
+    /// Empty `square`
+    pub const fn without_piece(mut self, square: Square) -> Self {
+        self.pieces[square as usize] = None;
+        self
+    }
+
+    pub const fn with_side_to_move(mut self, player: Player) -> Self {
+        self.side_to_move = player;
+        self
+    }
+
+    /// Let `player` castle in `direction` with the rook on `rook`, a corner in standard chess
+    pub const fn with_castle_rights(
+        mut self,
+        player: Player,
+        direction: CastleDirection,
+        rook: Square,
+    ) -> Self {
+        self.castle_rights[player as usize][direction as usize] = true;
+        self.castling_rooks = self.castling_rooks.with_square(player, direction, rook);
+        self
+    }
+
+    pub const fn without_castle_rights(
+        mut self,
+        player: Player,
+        direction: CastleDirection,
+    ) -> Self {
+        self.castle_rights[player as usize][direction as usize] = false;
+        self
+    }
+
+    /// The square a pawn that just double pushed skipped over
+    pub const fn with_en_passant(mut self, square: Option<Square>) -> Self {
+        self.en_passant = square;
+        self
+    }
+
+    pub const fn with_clocks(mut self, halfmove_clock: u16, fullmove_number: u16) -> Self {
+        self.halfmove_clock = halfmove_clock;
+        self.fullmove_number = fullmove_number;
+        self
+    }
+
+    fn is(&self, square: Square, player: Player, piece: PieceType) -> bool {
+        self.pieces[square as usize] == Some((player, piece))
+    }
+
+    /// The square of `player`'s only king
+    fn king(&self, player: Player) -> Result<Square, PositionError> {
+        let mut kings = SQUARES
+            .into_iter()
+            .filter(|&square| self.is(square, player, PieceType::King));
+        match (kings.next(), kings.count()) {
+            (Some(king), 0) => Ok(king),
+            (None, _) => Err(PositionError::KingCount { player, count: 0 }),
+            (Some(_), others) => Err(PositionError::KingCount {
+                player,
+                count: others + 1,
+            }),
+        }
+    }
+
+    fn check_castle_rights(
+        &self,
+        player: Player,
+        direction: CastleDirection,
+        king: Square,
+    ) -> Result<(), PositionError> {
+        let back_rank = match player {
+            Player::White => 0,
+            Player::Black => 7,
+        };
+        let rook = self.castling_rooks.square(player, direction);
+        let on_its_side = match direction {
+            CastleDirection::KingSide => rook.file() > king.file(),
+            CastleDirection::QueenSide => rook.file() < king.file(),
+        };
+        if king.rank() == back_rank
+            && rook.rank() == back_rank
+            && on_its_side
+            && self.is(rook, player, PieceType::Rook)
+        {
+            Ok(())
+        } else {
+            Err(PositionError::InvalidCastleRights { player, direction })
+        }
+    }
+
+    fn check_en_passant(&self, square: Square) -> Result<(), PositionError> {
+        let (rank, pushed_rank, from_rank) = match self.side_to_move {
+            Player::White => (5, 4, 6),
+            Player::Black => (2, 3, 1),
+        };
+        let file = square.file();
+        if square.rank() == rank
+            && self.pieces[square as usize].is_none()
+            && self.pieces[Square::from_file_and_rank(file, from_rank) as usize].is_none()
+            && self.is(
+                Square::from_file_and_rank(file, pushed_rank),
+                self.side_to_move.switch(),
+                PieceType::Pawn,
+            )
+        {
+            Ok(())
+        } else {
+            Err(PositionError::InvalidEnPassant(square))
+        }
+    }
+
+    /// Check the position is one a game could reach and build it
+    ///
+    /// Each side needs exactly one king, pawns must stay off the back ranks, the side not to move
+    /// must not be in check, every castling right needs its king on the back rank and its rook in
+    /// place, and an en passant square needs the pawn that skipped over it.
+    pub fn build(self) -> Result<Position, PositionError> {
+        let mut pieces =
+            PieceArrangement::new(self.king(Player::White)?, self.king(Player::Black)?);
+        for square in SQUARES {
+            let Some((player, piece)) = self.pieces[square as usize] else {
+                continue;
+            };
+            if piece == PieceType::Pawn && matches!(square.rank(), 0 | 7) {
+                return Err(PositionError::PawnOnBackRank(square));
+            }
+            if let Some(piece) = piece.to_non_king_piece_type() {
+                pieces = pieces.add_piece_for(player, piece, square);
+            }
+        }
+
+        let opponent_king = pieces.king_square_for_side(self.side_to_move.switch());
+        let attacked = match self.side_to_move {
+            Player::White => pieces.attacked_by::<true>(),
+            Player::Black => pieces.attacked_by::<false>(),
+        };
+        if attacked & opponent_king.to_bit() != EMPTY_BITBOARD {
+            return Err(PositionError::OpponentInCheck);
+        }
+
+        for player in [Player::White, Player::Black] {
+            for direction in [CastleDirection::KingSide, CastleDirection::QueenSide] {
+                if self.castle_rights[player as usize][direction as usize] {
+                    let king = pieces.king_square_for_side(player);
+                    self.check_castle_rights(player, direction, king)?;
+                }
+            }
+        }
+        if let Some(square) = self.en_passant {
+            self.check_en_passant(square)?;
+        }
+        if self.fullmove_number == 0 {
+            return Err(PositionError::ZeroFullmoveNumber);
+        }
+
+        Ok(Position::from_parts(
+            pieces,
+            self.side_to_move,
+            self.castle_rights,
+            self.castling_rooks,
+            self.en_passant,
+            self.halfmove_clock,
+            self.fullmove_number,
+        ))
+    }
+}
+
+impl Default for PositionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Start editing an existing position, such as one read from a FEN string
+impl From<Position> for PositionBuilder {
+    fn from(position: Position) -> Self {
+        let pieces = position.pieces();
+        let mut builder = SQUARES.into_iter().fold(Self::new(), |builder, square| {
+            match (pieces.player_on(square), pieces.piece_type_on(square)) {
+                (Some(player), Some(piece)) => builder.with_piece(square, player, piece),
+                _ => builder,
+            }
+        });
+        builder.side_to_move = position.side_to_move();
+        for player in [Player::White, Player::Black] {
+            for direction in [CastleDirection::KingSide, CastleDirection::QueenSide] {
+                if position.has_castle_rights(player, direction) {
+                    let rook = position.castling_rooks().square(player, direction);
+                    builder = builder.with_castle_rights(player, direction, rook);
+                }
+            }
+        }
+
+        builder
+            .with_en_passant(position.en_passant_square())
+            .with_clocks(position.halfmove_clock(), position.fullmove_number())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::any_board::AnyBoard;
+    use crate::castle_direction::CastleDirection;
+    use crate::fen::{Position, STARTING_FEN};
+    use crate::piece_type::PieceType;
+    use crate::player::Player;
+    use crate::position_builder::{PositionBuilder, PositionError};
+    use crate::square::Square::*;
+    use test_case::test_case;
+
+    fn kings() -> PositionBuilder {
+        PositionBuilder::new()
+            .with_piece(E1, Player::White, PieceType::King)
+            .with_piece(E8, Player::Black, PieceType::King)
+    }
+
+    #[test]
+    fn builds_a_position_piece_by_piece() {
+        let position = kings()
+            .with_piece(H1, Player::White, PieceType::Rook)
+            .with_piece(D5, Player::Black, PieceType::Pawn)
+            .with_piece(E5, Player::White, PieceType::Pawn)
+            .with_castle_rights(Player::White, CastleDirection::KingSide, H1)
+            .with_en_passant(Some(D6))
+            .with_clocks(0, 12)
+            .build()
+            .unwrap();
+        assert_eq!(position.fen(), "4k3/8/8/3pP3/8/8/8/4K2R w K d6 0 12");
+    }
+
+    #[test_case(STARTING_FEN; "starting position")]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"; "chess960")]
+    #[test_case("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"; "en passant")]
+    fn rebuilds_what_it_was_given(fen: &str) {
+        let position: Position = fen.parse().unwrap();
+        assert_eq!(PositionBuilder::from(position).build(), Ok(position));
+    }
+
+    #[test]
+    fn edits_an_existing_position() {
+        let position: Position = STARTING_FEN.parse().unwrap();
+        let board: AnyBoard = PositionBuilder::from(position)
+            .without_piece(B1)
+            .without_piece(C1)
+            .without_piece(D1)
+            .build()
+            .unwrap()
+            .into();
+        assert!(board.can_castle(CastleDirection::QueenSide));
+    }
+
+    #[test_case(PositionBuilder::new().with_piece(E8, Player::Black, PieceType::King), PositionError::KingCount { player: Player::White, count: 0 }; "no white king")]
+    #[test_case(kings().with_piece(A8, Player::Black, PieceType::King), PositionError::KingCount { player: Player::Black, count: 2 }; "two black kings")]
+    #[test_case(kings().with_piece(A8, Player::White, PieceType::Pawn), PositionError::PawnOnBackRank(A8); "pawn on the last rank")]
+    #[test_case(kings().with_piece(C1, Player::Black, PieceType::Pawn), PositionError::PawnOnBackRank(C1); "pawn on the first rank")]
+    #[test_case(kings().with_piece(E4, Player::White, PieceType::Rook), PositionError::OpponentInCheck; "opponent in check")]
+    #[test_case(kings().with_castle_rights(Player::White, CastleDirection::KingSide, H1), PositionError::InvalidCastleRights { player: Player::White, direction: CastleDirection::KingSide }; "rights without a rook")]
+    #[test_case(kings().with_piece(A1, Player::White, PieceType::Rook).with_castle_rights(Player::White, CastleDirection::KingSide, A1), PositionError::InvalidCastleRights { player: Player::White, direction: CastleDirection::KingSide }; "rook on the wrong side")]
+    #[test_case(kings().with_piece(A8, Player::White, PieceType::Rook).with_side_to_move(Player::Black).with_castle_rights(Player::Black, CastleDirection::QueenSide, A8), PositionError::InvalidCastleRights { player: Player::Black, direction: CastleDirection::QueenSide }; "rook of the wrong side")]
+    #[test_case(kings().without_piece(E1).with_piece(E2, Player::White, PieceType::King).with_piece(A1, Player::White, PieceType::Rook).with_castle_rights(Player::White, CastleDirection::QueenSide, A1), PositionError::InvalidCastleRights { player: Player::White, direction: CastleDirection::QueenSide }; "king off the back rank")]
+    #[test_case(kings().with_en_passant(Some(D6)), PositionError::InvalidEnPassant(D6); "en passant without a pawn")]
+    #[test_case(kings().with_piece(D5, Player::Black, PieceType::Pawn).with_en_passant(Some(D3)), PositionError::InvalidEnPassant(D3); "en passant on the wrong rank")]
+    #[test_case(kings().with_piece(D5, Player::Black, PieceType::Pawn).with_piece(D7, Player::Black, PieceType::Knight).with_en_passant(Some(D6)), PositionError::InvalidEnPassant(D6); "en passant from an occupied square")]
+    #[test_case(kings().with_clocks(0, 0), PositionError::ZeroFullmoveNumber; "zero fullmove number")]
+    fn rejects_illegal_positions(builder: PositionBuilder, error: PositionError) {
+        assert_eq!(builder.build(), Err(error));
+    }
+
+    #[test]
+    fn errors_describe_the_problem() {
+        let error = kings()
+            .with_piece(E4, Player::White, PieceType::Queen)
+            .with_piece(E5, Player::White, PieceType::King)
+            .build()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "white has 2 kings instead of one");
+    }
+}
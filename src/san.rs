@@ -0,0 +1,298 @@
+//! Standard Algebraic Notation, as used by people and in PGN files (`Nbd7`, `exd6`, `O-O-O`,
+//! `e8=Q+`)
+
+use crate::any_board::AnyBoard;
+use crate::castle_direction::CastleDirection;
+use crate::chess_move::{Move, MoveKind};
+use crate::move_generation::Termination;
+use crate::piece_type::{PieceType, PromotionPieceType};
+use crate::square::Square;
+
+use core::fmt::{self, Display, Formatter};
+
+/// Why a string could not be read as a move in the position
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SanError {
+    /// The string is not SAN at all
+    Invalid,
+    /// The string is SAN but no legal move matches it
+    Illegal,
+    /// More than one legal move matches, as `Nd7` does with knights on b8 and f6
+    Ambiguous,
+}
+
+impl Display for SanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SanError::Invalid => "not a move in standard algebraic notation",
+            SanError::Illegal => "no legal move matches",
+            SanError::Ambiguous => "more than one legal move matches",
+        })
+    }
+}
+
+impl std::error::Error for SanError {}
+
+const fn piece_letter(piece: PieceType) -> Option<char> {
+    match piece {
+        PieceType::Pawn => None,
+        PieceType::Knight => Some('N'),
+        PieceType::Bishop => Some('B'),
+        PieceType::Rook => Some('R'),
+        PieceType::Queen => Some('Q'),
+        PieceType::King => Some('K'),
+    }
+}
+
+const fn piece_from_letter(letter: char) -> Option<PieceType> {
+    match letter {
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'R' => Some(PieceType::Rook),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+const fn promotion_from_letter(letter: char) -> Option<PromotionPieceType> {
+    match letter.to_ascii_uppercase() {
+        'N' => Some(PromotionPieceType::Knight),
+        'B' => Some(PromotionPieceType::Bishop),
+        'R' => Some(PromotionPieceType::Rook),
+        'Q' => Some(PromotionPieceType::Queen),
+        _ => None,
+    }
+}
+
+const fn is_capture(kind: MoveKind) -> bool {
+    matches!(
+        kind,
+        MoveKind::Capture | MoveKind::EnPassant | MoveKind::PromotionCapture(_)
+    )
+}
+
+/// Write `mv`, which must be one of `board`'s legal moves, in SAN
+///
+/// A piece move names the file of the piece when another piece of the same type could move to
+/// the same square, the rank when they share the file, and both when neither tells them apart.
+pub fn format_san(board: &AnyBoard, mv: Move) -> String {
+    let (from, to) = (mv.from(), mv.to());
+    let mut san = String::with_capacity(8);
+    match mv.kind() {
+        MoveKind::Castle(CastleDirection::KingSide) => san.push_str("O-O"),
+        MoveKind::Castle(CastleDirection::QueenSide) => san.push_str("O-O-O"),
+        kind => {
+            let piece = board.pieces().piece_type_on(from);
+            match piece.and_then(piece_letter) {
+                Some(letter) => {
+                    san.push(letter);
+                    let rivals = board
+                        .legal_moves()
+                        .iter()
+                        .filter(|other| {
+                            other.to() == to
+                                && other.from() != from
+                                && !matches!(other.kind(), MoveKind::Castle(_))
+                                && board.pieces().piece_type_on(other.from()) == piece
+                        })
+                        .map(|other| other.from())
+                        .collect::<Vec<_>>();
+                    let file = (b'a' + from.file()) as char;
+                    let rank = (b'1' + from.rank()) as char;
+                    if !rivals.is_empty() {
+                        if rivals.iter().all(|other| other.file() != from.file()) {
+                            san.push(file);
+                        } else if rivals.iter().all(|other| other.rank() != from.rank()) {
+                            san.push(rank);
+                        } else {
+                            san.push(file);
+                            san.push(rank);
+                        }
+                    }
+                }
+                None if is_capture(kind) => san.push((b'a' + from.file()) as char),
+                None => {}
+            }
+            if is_capture(kind) {
+                san.push('x');
+            }
+            san.push_str(&to.to_string());
+            if let Some(promotion) = mv.promotion() {
+                san.push('=');
+                san.extend(piece_letter(promotion.to_piece_type()));
+            }
+        }
+    }
+
+    let after = board.make_move(mv);
+    if after.in_check() {
+        san.push(match after.termination() {
+            Some(Termination::Checkmate) => '#',
+            _ => '+',
+        });
+    }
+
+    san
+}
+
+/// Find the legal move `san` stands for in `board`
+///
+/// This accepts what people and other programs write besides strict SAN: a missing or extra
+/// capture sign, zeros for castling, promotions with or without `=`, the `e.p.` suffix, a dash
+/// between the squares, and trailing check, mate and annotation marks such as `+`, `#` or `!?`.
+pub fn parse_san(board: &AnyBoard, san: &str) -> Result<Move, SanError> {
+    let san = san
+        .trim()
+        .trim_end_matches(['+', '#', '!', '?'])
+        .trim_end_matches("e.p.")
+        .trim_end();
+    let legal_moves = board.legal_moves();
+    let matching = |filter: &dyn Fn(&Move) -> bool| {
+        let mut matches = legal_moves.iter().copied().filter(filter);
+        match (matches.next(), matches.next()) {
+            (Some(mv), None) => Ok(mv),
+            (None, _) => Err(SanError::Illegal),
+            (Some(_), Some(_)) => Err(SanError::Ambiguous),
+        }
+    };
+
+    let castle = match san {
+        "O-O" | "0-0" => Some(CastleDirection::KingSide),
+        "O-O-O" | "0-0-0" => Some(CastleDirection::QueenSide),
+        _ => None,
+    };
+    if let Some(direction) = castle {
+        return matching(&|mv| mv.kind() == MoveKind::Castle(direction));
+    }
+
+    let mut chars = san
+        .chars()
+        .filter(|c| !matches!(c, 'x' | ':' | '-' | '='))
+        .collect::<Vec<_>>();
+    let piece = match chars.first().copied().and_then(piece_from_letter) {
+        Some(piece) => {
+            chars.remove(0);
+            piece
+        }
+        None => PieceType::Pawn,
+    };
+    let promotion = match chars.last() {
+        Some(&letter) if piece == PieceType::Pawn && !letter.is_ascii_digit() => {
+            chars.pop();
+            Some(promotion_from_letter(letter).ok_or(SanError::Invalid)?)
+        }
+        _ => None,
+    };
+    if !(2..=4).contains(&chars.len()) {
+        return Err(SanError::Invalid);
+    }
+    let to = chars[chars.len() - 2..]
+        .iter()
+        .collect::<String>()
+        .parse::<Square>()
+        .map_err(|_| SanError::Invalid)?;
+    let (mut from_file, mut from_rank) = (None, None);
+    for &c in &chars[..chars.len() - 2] {
+        match c {
+            'a'..='h' if from_file.is_none() && from_rank.is_none() => {
+                from_file = Some(c as u8 - b'a')
+            }
+            '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+            _ => return Err(SanError::Invalid),
+        }
+    }
+    // A pawn only leaves its file to capture, which names the file it came from
+    if piece == PieceType::Pawn && from_file.is_none() {
+        from_file = Some(to.file());
+    }
+
+    matching(&|mv| {
+        mv.to() == to
+            && !matches!(mv.kind(), MoveKind::Castle(_))
+            && board.pieces().piece_type_on(mv.from()) == Some(piece)
+            && from_file.is_none_or(|file| mv.from().file() == file)
+            && from_rank.is_none_or(|rank| mv.from().rank() == rank)
+            && mv.promotion() == promotion
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::any_board::AnyBoard;
+    use crate::fen::STARTING_FEN;
+    use crate::san::{format_san, parse_san, SanError};
+    use test_case::test_case;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    /// Black knights on b8 and f6 can both go to d7
+    const TWO_KNIGHTS: &str = "rnbqkb1r/ppp1pppp/5n2/3p4/3P4/5N2/PPP1PPPP/RNBQKB1R b KQkq - 2 3";
+
+    #[test_case(STARTING_FEN, "g1f3", "Nf3"; "knight move")]
+    #[test_case(STARTING_FEN, "e2e4", "e4"; "pawn push")]
+    #[test_case(KIWIPETE, "e1g1", "O-O"; "king side castle")]
+    #[test_case(KIWIPETE, "e1c1", "O-O-O"; "queen side castle")]
+    #[test_case(KIWIPETE, "d5e6", "dxe6"; "pawn capture")]
+    #[test_case("3qk3/8/8/8/8/8/8/3RK3 w - - 0 1", "d1d8", "Rxd8+"; "capture with check")]
+    #[test_case(KIWIPETE, "c3b1", "Nb1"; "unambiguous knight")]
+    #[test_case(KIWIPETE, "e5d3", "Nd3"; "knight move one knight can make")]
+    #[test_case("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", "b1c3", "Nc3"; "other knight cannot reach")]
+    #[test_case("4k3/8/8/8/8/8/8/R4RK1 w - - 0 1", "a1d1", "Rad1"; "file disambiguation")]
+    #[test_case(TWO_KNIGHTS, "b8d7", "Nbd7"; "knights on different files")]
+    #[test_case("4k3/R7/8/8/8/8/8/R3K3 w - - 0 1", "a1a4", "R1a4"; "rank disambiguation")]
+    #[test_case("4k3/8/8/8/8/Q1Q5/8/Q3K3 w - - 0 1", "a3b2", "Qa3b2"; "file and rank disambiguation")]
+    #[test_case("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1", "d5e6", "dxe6"; "en passant")]
+    #[test_case("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8q", "e8=Q"; "promotion")]
+    #[test_case("2k5/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8r", "e8=R+"; "promotion with check")]
+    #[test_case("rnbqkbnr/pppp1ppp/8/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 0 1", "f3f7", "Qxf7#"; "scholar's mate")]
+    fn formats(fen: &str, uci: &str, san: &str) {
+        let board: AnyBoard = fen.parse().unwrap();
+        let mv = board.legal_moves().find_uci(uci.parse().unwrap()).unwrap();
+        assert_eq!(format_san(&board, mv), san);
+        assert_eq!(parse_san(&board, san), Ok(mv));
+    }
+
+    #[test_case(STARTING_FEN, "Ng1f3", "g1f3"; "long algebraic")]
+    #[test_case(STARTING_FEN, "Ng1-f3", "g1f3"; "long algebraic with dash")]
+    #[test_case(STARTING_FEN, "e4!?", "e2e4"; "annotation")]
+    #[test_case(KIWIPETE, "0-0-0", "e1c1"; "castle with zeros")]
+    #[test_case(KIWIPETE, "Qf6", "f3f6"; "missing capture sign")]
+    #[test_case(KIWIPETE, "de6", "d5e6"; "pawn capture without sign")]
+    #[test_case("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6e.p.", "e5d6"; "en passant suffix")]
+    #[test_case("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6 e.p.", "e5d6"; "spaced en passant suffix")]
+    #[test_case("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e8Q", "e7e8q"; "promotion without equals")]
+    #[test_case("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e8=n", "e7e8n"; "lower case promotion")]
+    fn parses_loose_san(fen: &str, san: &str, uci: &str) {
+        let board: AnyBoard = fen.parse().unwrap();
+        let mv = board.legal_moves().find_uci(uci.parse().unwrap()).unwrap();
+        assert_eq!(parse_san(&board, san), Ok(mv));
+    }
+
+    #[test_case(STARTING_FEN, ""; "empty")]
+    #[test_case(STARTING_FEN, "Zf3"; "unknown piece")]
+    #[test_case(STARTING_FEN, "Nf9"; "off the board")]
+    #[test_case(STARTING_FEN, "e8=K"; "king promotion")]
+    #[test_case(STARTING_FEN, "Nbgf3"; "too many disambiguators")]
+    fn rejects_invalid(fen: &str, san: &str) {
+        let board: AnyBoard = fen.parse().unwrap();
+        assert_eq!(parse_san(&board, san), Err(SanError::Invalid));
+    }
+
+    #[test_case(STARTING_FEN, "e5"; "pawn too far")]
+    #[test_case(STARTING_FEN, "O-O"; "castle through pieces")]
+    #[test_case(STARTING_FEN, "Nd4"; "no knight reaches")]
+    #[test_case("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e8"; "promotion without a piece")]
+    #[test_case("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1", "Nc3"; "pinned knight")]
+    fn rejects_illegal(fen: &str, san: &str) {
+        let board: AnyBoard = fen.parse().unwrap();
+        assert_eq!(parse_san(&board, san), Err(SanError::Illegal));
+    }
+
+    #[test_case("4k3/8/8/8/8/8/8/R4RK1 w - - 0 1", "Rd1"; "rooks on one rank")]
+    #[test_case("4k3/8/8/8/8/Q1Q5/8/Q3K3 w - - 0 1", "Qab2"; "queens on one file")]
+    #[test_case(TWO_KNIGHTS, "Nd7"; "knights")]
+    fn rejects_ambiguous(fen: &str, san: &str) {
+        let board: AnyBoard = fen.parse().unwrap();
+        assert_eq!(parse_san(&board, san), Err(SanError::Ambiguous));
+    }
+}
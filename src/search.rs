@@ -0,0 +1,777 @@
+use crate::any_board::AnyBoard;
+use crate::chess_move::{Move, MoveKind};
+use crate::evaluation::{evaluate, piece_value};
+use crate::piece_type::PieceType;
+use crate::see::see_ge;
+use crate::square::Square;
+use crate::time_management::{TimeBudget, TimeManager};
+use crate::transposition_table::{Bound, Entry, TranspositionTable};
+
+use core::cmp::Reverse;
+use core::fmt::{self, Display, Formatter};
+use core::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Instant;
+
+/// Deeper than any search will go, this bounds the principal variation and mate distances
+pub const MAX_PLY: usize = 128;
+/// Mate on the board, mate in `n` plies scores `MATE - n`
+const MATE: i32 = 30_000;
+const INFINITY: i32 = MATE + 1;
+/// Nodes searched between looks at the clock and the stop flag
+const CHECK_INTERVAL: u64 = 1024;
+
+/// A search score from the point of view of the side to move
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Score {
+    Centipawns(i32),
+    /// Mate in this many moves, negative when the side to move is getting mated
+    Mate(i32),
+}
+
+/// Mate scores count plies from the root, but the table is shared between positions at any ply,
+/// so mates are stored counting from the position itself
+const fn score_to_table(score: i32, ply: usize) -> i16 {
+    let score = if score > MATE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score < -(MATE - MAX_PLY as i32) {
+        score - ply as i32
+    } else {
+        score
+    };
+    score as i16
+}
+
+/// Undo [`score_to_table`]
+const fn score_from_table(score: i16, ply: usize) -> i32 {
+    let score = score as i32;
+    if score > MATE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score < -(MATE - MAX_PLY as i32) {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+impl Score {
+    fn from_internal(score: i32) -> Self {
+        if score > MATE - MAX_PLY as i32 {
+            Score::Mate((MATE - score + 1) / 2)
+        } else if score < -(MATE - MAX_PLY as i32) {
+            Score::Mate(-(MATE + score) / 2)
+        } else {
+            Score::Centipawns(score)
+        }
+    }
+}
+
+/// Formatted as in a UCI `info` line, `cp 35` or `mate -2`
+impl Display for Score {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Centipawns(cp) => write!(f, "cp {cp}"),
+            Score::Mate(moves) => write!(f, "mate {moves}"),
+        }
+    }
+}
+
+/// When to give up, a search without limits runs until its stop flag is set
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<TimeBudget>,
+}
+
+/// Progress reported after every completed iteration
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SearchInfo {
+    pub depth: u32,
+    /// The deepest ply any line reached
+    pub seldepth: u32,
+    pub nodes: u64,
+    pub time: Duration,
+    pub score: Score,
+    pub pv: Vec<Move>,
+    /// How full the transposition table is, in permille
+    pub hashfull: u16,
+}
+
+impl SearchInfo {
+    /// Nodes per second
+    pub fn nps(&self) -> u64 {
+        (self.nodes as u128 * 1000 / self.time.as_millis().max(1)) as u64
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SearchResult {
+    /// `None` only when the side to move has no legal moves
+    pub best_move: Option<Move>,
+    pub score: Score,
+    pub pv: Vec<Move>,
+    /// The last depth searched to completion, 0 if even the first iteration was cut short
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+/// A principal variation collected on the stack as the search unwinds
+#[derive(Copy, Clone)]
+struct PvLine {
+    moves: [Move; MAX_PLY],
+    len: usize,
+}
+
+impl PvLine {
+    const fn new() -> Self {
+        Self {
+            moves: [Move::new(Square::A1, Square::A1, MoveKind::Quiet); MAX_PLY],
+            len: 0,
+        }
+    }
+
+    /// Replace this line with `mv` followed by `rest`
+    fn update(&mut self, mv: Move, rest: &PvLine) {
+        let len = (rest.len + 1).min(MAX_PLY);
+        self.moves[0] = mv;
+        self.moves[1..len].copy_from_slice(&rest.moves[..len - 1]);
+        self.len = len;
+    }
+
+    fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+/// Higher for moves worth searching first: the best move found before, then captures that
+/// do not lose material by most valuable victim and least valuable attacker, then promotions and
+/// quiet moves, and losing captures last
+fn move_order_key(board: &AnyBoard, mv: Move, best_move: Option<Move>) -> i32 {
+    if best_move == Some(mv) {
+        return i32::MAX;
+    }
+    let attacker = board
+        .pieces()
+        .piece_type_on(mv.from())
+        .map_or(0, piece_value);
+    let victim = match mv.kind() {
+        MoveKind::EnPassant => piece_value(PieceType::Pawn),
+        _ if mv.is_capture() => board.pieces().piece_type_on(mv.to()).map_or(0, piece_value),
+        _ => 0,
+    };
+    let promotion = mv
+        .promotion()
+        .map_or(0, |piece| piece_value(piece.to_piece_type()));
+    let capture = match mv.is_capture() {
+        true if see_ge(board, mv, 0) => 10 * victim - attacker + 10_000,
+        true => 10 * victim - attacker - 20_000,
+        false => 0,
+    };
+
+    capture + promotion
+}
+
+struct Searcher<'a> {
+    limits: Limits,
+    /// Shared with every other search, `None` to search without one
+    table: Option<&'a TranspositionTable>,
+    stop: &'a AtomicBool,
+    /// 0 for the main thread, which alone reports progress and enforces the limits
+    thread: usize,
+    start: Instant,
+    nodes: u64,
+    /// The nodes searched by every thread, which this thread adds its own to now and then
+    total_nodes: &'a AtomicU64,
+    /// The part of `nodes` already added to `total_nodes`
+    reported_nodes: u64,
+    seldepth: u32,
+    /// Set once a limit is hit, after which every score is meaningless
+    aborted: bool,
+    /// The previous iteration's principal variation, tried first at each ply
+    previous_pv: Vec<Move>,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(
+        limits: Limits,
+        table: Option<&'a TranspositionTable>,
+        stop: &'a AtomicBool,
+        thread: usize,
+        total_nodes: &'a AtomicU64,
+    ) -> Self {
+        Self {
+            limits,
+            table,
+            stop,
+            thread,
+            start: Instant::now(),
+            nodes: 0,
+            total_nodes,
+            reported_nodes: 0,
+            seldepth: 0,
+            aborted: false,
+            previous_pv: Vec::new(),
+        }
+    }
+
+    /// Add the nodes searched since the last call to the total over every thread, and return it
+    fn report_nodes(&mut self) -> u64 {
+        let unreported = self.nodes - self.reported_nodes;
+        self.reported_nodes = self.nodes;
+        self.total_nodes.fetch_add(unreported, Ordering::Relaxed) + unreported
+    }
+
+    fn should_abort(&mut self) -> bool {
+        if let Some(nodes) = self.limits.nodes {
+            let unreported = self.nodes - self.reported_nodes;
+            if self.total_nodes.load(Ordering::Relaxed) + unreported >= nodes {
+                return true;
+            }
+        }
+        if !self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            return false;
+        }
+        self.report_nodes();
+        self.stop.load(Ordering::Relaxed)
+            || self
+                .limits
+                .time
+                .is_some_and(|time| self.start.elapsed() >= time.hard)
+    }
+
+    /// Fail-soft negamax alpha-beta with principal variation search, `pv` receives the best line
+    fn negamax(
+        &mut self,
+        board: AnyBoard,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut PvLine,
+    ) -> i32 {
+        pv.len = 0;
+        if self.aborted || self.should_abort() {
+            self.aborted = true;
+            return 0;
+        }
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply as u32);
+
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            return if board.in_check() {
+                -MATE + ply as i32
+            } else {
+                0
+            };
+        }
+        if ply > 0 && board.halfmove_clock() >= 100 {
+            return 0;
+        }
+        if depth == 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+
+        let key = board.zobrist();
+        let entry = self.table.and_then(|table| table.probe(key));
+        // Cut off only outside the principal variation, whose line the table cannot give back
+        if let Some(entry) = entry.filter(|_| ply > 0 && beta - alpha == 1) {
+            let score = score_from_table(entry.score, ply);
+            if entry.depth as u32 >= depth
+                && match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => score >= beta,
+                    Bound::Upper => score <= alpha,
+                }
+            {
+                return score;
+            }
+        }
+
+        let best_move = entry
+            .and_then(|entry| entry.best_move)
+            .or_else(|| self.previous_pv.get(ply).copied());
+        moves
+            .as_mut_slice()
+            .sort_by_cached_key(|&mv| Reverse(move_order_key(&board, mv, best_move)));
+        // Helper threads try the root moves after the best in different orders, so they spread
+        // out over the tree instead of all searching the same lines
+        if ply == 0 && moves.len() > 2 {
+            let rest = &mut moves.as_mut_slice()[1..];
+            let len = rest.len();
+            rest.rotate_left(self.thread % len);
+        }
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut line = PvLine::new();
+        for (i, &mv) in moves.iter().enumerate() {
+            let child = board.make_move(mv);
+            let mut score = if i == 0 {
+                -self.negamax(child, depth - 1, ply + 1, -beta, -alpha, &mut line)
+            } else {
+                // Expect the first move to stay best and only prove the rest are no better
+                -self.negamax(child, depth - 1, ply + 1, -alpha - 1, -alpha, &mut line)
+            };
+            if i > 0 && score > alpha && score < beta {
+                score = -self.negamax(child, depth - 1, ply + 1, -beta, -alpha, &mut line);
+            }
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    best_move = Some(mv);
+                    pv.update(mv, &line);
+                }
+                if score >= beta {
+                    break;
+                }
+            }
+        }
+
+        if let Some(table) = self.table {
+            let bound = if best >= beta {
+                Bound::Lower
+            } else if best > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+            let entry = Entry {
+                best_move,
+                depth: depth as u8,
+                score: score_to_table(best, ply),
+                bound,
+            };
+            table.store(key, entry);
+        }
+
+        best
+    }
+
+    /// Search only captures and promotions until the position is quiet, so the static evaluation
+    /// is never taken in the middle of an exchange
+    ///
+    /// The side to move may stand pat on the evaluation instead of capturing, unless it is in check
+    /// when every evasion is searched. Captures that lose material by [`see_ge`] are skipped.
+    fn quiescence(&mut self, board: AnyBoard, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.aborted || self.should_abort() {
+            self.aborted = true;
+            return 0;
+        }
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply as u32);
+
+        let mut moves = board.legal_moves();
+        let in_check = board.in_check();
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+        if ply >= MAX_PLY - 1 {
+            return evaluate(&board);
+        }
+
+        let mut best = -INFINITY;
+        if !in_check {
+            best = evaluate(&board);
+            if best >= beta {
+                return best;
+            }
+            alpha = alpha.max(best);
+        }
+
+        moves
+            .as_mut_slice()
+            .sort_by_cached_key(|&mv| Reverse(move_order_key(&board, mv, None)));
+        for &mv in moves.iter() {
+            if !in_check && !(mv.is_promotion() || mv.is_capture() && see_ge(&board, mv, 0)) {
+                continue;
+            }
+
+            let score = -self.quiescence(board.make_move(mv), ply + 1, -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+                if score >= beta {
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+}
+
+/// The score of `board` for the side to move once every capture and promotion worth making has
+/// been played out
+pub fn quiescence(board: AnyBoard) -> i32 {
+    let stop = AtomicBool::new(false);
+    let nodes = AtomicU64::new(0);
+    let mut searcher = Searcher::new(Limits::default(), None, &stop, 0, &nodes);
+    searcher.quiescence(board, 0, -INFINITY, INFINITY)
+}
+
+impl Searcher<'_> {
+    /// Search deeper and deeper from `first_depth` until aborted, calling `on_info` after each
+    /// completed depth with the nodes of every thread
+    fn iterative_deepening(
+        &mut self,
+        board: AnyBoard,
+        first_depth: u32,
+        mut on_info: impl FnMut(&SearchInfo),
+    ) -> SearchResult {
+        let legal_moves = board.legal_moves();
+        let mut time_manager = self.limits.time.map(TimeManager::new);
+        let mut result = SearchResult {
+            best_move: legal_moves.first().copied(),
+            score: Score::Centipawns(0),
+            pv: Vec::new(),
+            depth: 0,
+            nodes: 0,
+        };
+
+        let max_depth = self
+            .limits
+            .depth
+            .unwrap_or(u32::MAX)
+            .min(MAX_PLY as u32 - 1);
+        for depth in first_depth..=max_depth {
+            self.seldepth = 0;
+            let mut pv = PvLine::new();
+            let score = self.negamax(board, depth, 0, -INFINITY, INFINITY, &mut pv);
+            if self.aborted {
+                break;
+            }
+
+            let info = SearchInfo {
+                depth,
+                seldepth: self.seldepth,
+                nodes: self.report_nodes(),
+                time: self.start.elapsed(),
+                score: Score::from_internal(score),
+                pv: pv.as_slice().to_vec(),
+                hashfull: self.table.map_or(0, TranspositionTable::hashfull),
+            };
+            on_info(&info);
+            result = SearchResult {
+                best_move: info.pv.first().copied().or(result.best_move),
+                score: info.score,
+                pv: info.pv,
+                depth,
+                nodes: info.nodes,
+            };
+            self.previous_pv.clone_from(&result.pv);
+            if result.best_move.is_none() {
+                break;
+            }
+            if let Some(time_manager) = &mut time_manager {
+                // With only one move to play there is nothing to spend the time on
+                if legal_moves.len() == 1
+                    || time_manager.should_stop(self.start.elapsed(), result.best_move, score)
+                {
+                    break;
+                }
+            }
+        }
+        self.report_nodes();
+
+        result
+    }
+}
+
+/// Search `board` by iterative deepening on `threads` threads until a limit is hit or `stop` is
+/// set, calling `on_info` after each depth the main thread completes
+///
+/// The threads share nothing but `table`, through which each finds what the others learnt: this
+/// is Lazy SMP. Helper threads start at alternating depths and order the root moves differently,
+/// and run until the main thread, which alone keeps to `limits`, is done. Results are left in
+/// `table` for any later search sharing it.
+///
+/// An interrupted iteration is thrown away, so the result always comes from a completed depth
+/// unless the first one could not finish, in which case it falls back to any legal move.
+pub fn search(
+    board: AnyBoard,
+    limits: Limits,
+    table: &TranspositionTable,
+    threads: usize,
+    stop: &AtomicBool,
+    on_info: impl FnMut(&SearchInfo),
+) -> SearchResult {
+    table.new_search();
+    let total_nodes = AtomicU64::new(0);
+    let helpers_stop = AtomicBool::new(false);
+
+    let mut result = thread::scope(|scope| {
+        for thread in 1..threads {
+            let (total_nodes, helpers_stop) = (&total_nodes, &helpers_stop);
+            scope.spawn(move || {
+                let limits = Limits::default();
+                let mut helper =
+                    Searcher::new(limits, Some(table), helpers_stop, thread, total_nodes);
+                helper.iterative_deepening(board, 1 + thread as u32 % 2, |_| {});
+            });
+        }
+
+        let mut main = Searcher::new(limits, Some(table), stop, 0, &total_nodes);
+        let result = main.iterative_deepening(board, 1, on_info);
+        helpers_stop.store(true, Ordering::Relaxed);
+        result
+    });
+    result.nodes = total_nodes.load(Ordering::Relaxed);
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::any_board::AnyBoard;
+    use crate::evaluation::evaluate;
+    use crate::search::{quiescence, search, Limits, Score, SearchInfo};
+    use crate::time_management::TimeBudget;
+    use crate::transposition_table::TranspositionTable;
+    use core::time::Duration;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Instant;
+    use test_case::test_case;
+
+    fn search_depth(fen: &str, depth: u32) -> (crate::search::SearchResult, Vec<SearchInfo>) {
+        let mut infos = Vec::new();
+        let limits = Limits {
+            depth: Some(depth),
+            ..Limits::default()
+        };
+        let result = search(
+            fen.parse().unwrap(),
+            limits,
+            &TranspositionTable::new(1),
+            1,
+            &AtomicBool::new(false),
+            |info| infos.push(info.clone()),
+        );
+        (result, infos)
+    }
+
+    #[test_case("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2, "a1a8", Score::Mate(1); "back rank mate in one")]
+    #[test_case("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", 4, "b1b7", Score::Mate(2); "rook ladder mate in two")]
+    #[test_case("7k/1R6/R7/8/8/8/8/6K1 b - - 0 1", 3, "h8g8", Score::Mate(-1); "getting mated in one")]
+    fn finds_the_best_move(fen: &str, depth: u32, best_move: &str, score: Score) {
+        let (result, _) = search_depth(fen, depth);
+        assert_eq!(result.best_move.unwrap().to_string(), best_move);
+        assert_eq!(result.score, score);
+    }
+
+    #[test]
+    fn wins_the_hanging_queen() {
+        let (result, _) = search_depth("4k3/8/8/3q4/8/8/3R4/3K4 w - - 0 1", 3);
+        assert_eq!(result.best_move.unwrap().to_string(), "d2d5");
+        assert!(matches!(result.score, Score::Centipawns(cp) if cp > 400));
+    }
+
+    #[test_case("4k3/8/4p3/3p4/8/8/8/3RK3 w - - 0 1", &[]; "declines a losing capture")]
+    #[test_case("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", &["d1d5"]; "takes a hanging queen")]
+    #[test_case("4k3/8/8/3q4/8/8/8/3RK3 b - - 0 1", &[]; "stands pat")]
+    #[test_case("4k3/8/2n5/3p4/8/2N5/8/3RK3 w - - 0 1", &["d1d5"]; "wins a pawn")]
+    fn quiescence_resolves_captures(fen: &str, captures: &[&str]) {
+        let board: AnyBoard = fen.parse().unwrap();
+        let resolved = captures.iter().fold(board, |board, uci| {
+            let mv = board.legal_moves().find_uci(uci.parse().unwrap()).unwrap();
+            board.make_move(mv)
+        });
+        let sign = if captures.len().is_multiple_of(2) {
+            1
+        } else {
+            -1
+        };
+        assert_eq!(quiescence(board), sign * evaluate(&resolved));
+    }
+
+    #[test]
+    fn sees_the_recapture_at_the_horizon() {
+        let (result, _) = search_depth("4k3/8/4p3/3p4/8/8/3Q4/4K3 w - - 0 1", 1);
+        assert_ne!(result.best_move.unwrap().to_string(), "d2d5");
+    }
+
+    #[test]
+    fn reports_every_iteration() {
+        let (result, infos) = search_depth(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            4,
+        );
+        assert_eq!(
+            infos.iter().map(|info| info.depth).collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+        assert!(infos.windows(2).all(|pair| pair[0].nodes < pair[1].nodes));
+        let last = infos.last().unwrap();
+        assert_eq!(last.pv, result.pv);
+        assert_eq!(last.pv.len(), 4);
+        assert!(last.seldepth >= 4);
+    }
+
+    #[test]
+    fn principal_variation_is_playable() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let (result, _) = search_depth(fen, 3);
+        assert_eq!(result.best_move, result.pv.first().copied());
+        result
+            .pv
+            .iter()
+            .fold(fen.parse::<AnyBoard>().unwrap(), |board, &mv| {
+                assert!(board.legal_moves().contains(&mv), "{mv} in {}", board.fen());
+                board.make_move(mv)
+            });
+    }
+
+    #[test]
+    fn no_moves_means_no_best_move() {
+        let (result, _) = search_depth("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3);
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, Score::Centipawns(0));
+    }
+
+    #[test]
+    fn table_carries_over_between_searches() {
+        let board = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+            .parse()
+            .unwrap();
+        let limits = Limits {
+            depth: Some(5),
+            ..Limits::default()
+        };
+        let table = TranspositionTable::new(1);
+        let stop = AtomicBool::new(false);
+        let mut hashfull = 0;
+        let first = search(board, limits, &table, 1, &stop, |info| {
+            hashfull = info.hashfull
+        });
+        assert!(hashfull > 0);
+        let second = search(board, limits, &table, 1, &stop, |_| {});
+        assert_eq!(first.best_move, second.best_move);
+        assert_eq!(first.score, second.score);
+        assert!(
+            second.nodes < first.nodes / 2,
+            "{} {}",
+            first.nodes,
+            second.nodes
+        );
+    }
+
+    #[test]
+    fn helper_threads_share_the_search() {
+        let board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+            .parse()
+            .unwrap();
+        let limits = Limits {
+            depth: Some(4),
+            ..Limits::default()
+        };
+        let table = TranspositionTable::new(4);
+        let mut infos = Vec::new();
+        let result = search(board, limits, &table, 4, &AtomicBool::new(false), |info| {
+            infos.push(info.clone())
+        });
+        assert_eq!(
+            infos.iter().map(|info| info.depth).collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+        assert!(infos.windows(2).all(|pair| pair[0].nodes <= pair[1].nodes));
+        // Every thread's nodes count, including those searched after the last report
+        assert!(result.nodes >= infos.last().unwrap().nodes);
+        assert!(board.legal_moves().contains(&result.best_move.unwrap()));
+
+        let (mate, _) = search_depth("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", 4);
+        let threaded = search(
+            "7k/8/8/8/8/8/R7/1R4K1 w - - 0 1".parse().unwrap(),
+            limits,
+            &TranspositionTable::new(1),
+            3,
+            &AtomicBool::new(false),
+            |_| {},
+        );
+        assert_eq!(threaded.score, mate.score);
+    }
+
+    #[test]
+    fn stop_ends_every_thread() {
+        let stop = AtomicBool::new(false);
+        let table = TranspositionTable::new(1);
+        let result = std::thread::scope(|scope| {
+            let search = scope.spawn(|| {
+                search(
+                    AnyBoard::default(),
+                    Limits::default(),
+                    &table,
+                    3,
+                    &stop,
+                    |_| {},
+                )
+            });
+            std::thread::sleep(Duration::from_millis(50));
+            stop.store(true, std::sync::atomic::Ordering::Relaxed);
+            search.join().unwrap()
+        });
+        assert!(result.depth > 0);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn keeps_to_the_time_budget() {
+        let table = TranspositionTable::new(1);
+        let stop = AtomicBool::new(false);
+        let budget = TimeBudget {
+            soft: Duration::from_millis(40),
+            hard: Duration::from_millis(100),
+        };
+        let limits = Limits {
+            time: Some(budget),
+            ..Limits::default()
+        };
+
+        let start = Instant::now();
+        let result = search(AnyBoard::default(), limits, &table, 2, &stop, |_| {});
+        assert!(start.elapsed() < Duration::from_millis(300));
+        assert!(result.depth > 0);
+
+        // A forced move is played at once, whatever the budget
+        let limits = Limits {
+            time: Some(TimeBudget {
+                soft: Duration::from_secs(60),
+                hard: Duration::from_secs(60),
+            }),
+            ..Limits::default()
+        };
+        let forced = "7k/8/8/8/8/8/q7/6rK w - - 0 1".parse().unwrap();
+        let result = search(forced, limits, &table, 1, &stop, |_| {});
+        assert_eq!(result.depth, 1);
+        assert_eq!(result.best_move.unwrap().to_string(), "h1g1");
+    }
+
+    #[test]
+    fn respects_node_limit_and_stop() {
+        let board = AnyBoard::default();
+        let limits = Limits {
+            nodes: Some(5_000),
+            ..Limits::default()
+        };
+        let table = TranspositionTable::new(1);
+        let result = search(board, limits, &table, 1, &AtomicBool::new(false), |_| {});
+        assert!(result.nodes <= 5_000);
+        assert!(result.best_move.is_some());
+
+        // Even a search stopped before it starts has a move to play
+        let stop = AtomicBool::new(true);
+        let result = search(board, Limits::default(), &table, 1, &stop, |_| {});
+        assert_eq!(result.depth, 0);
+        assert!(result.best_move.is_some());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::any_board::AnyBoard;
+use crate::attacks::{bishop_attacks, rook_attacks};
+use crate::bitboard::Bitboard;
+use crate::chess_move::{Move, MoveKind};
+use crate::evaluation::piece_value;
+use crate::piece_arrangement::PieceArrangement;
+use crate::piece_type::{NonKingPieceType, PieceType};
+use crate::square::Square;
+
+/// The most captures one exchange can take, one per piece on the board
+const MAX_EXCHANGE: usize = 32;
+
+/// The least valuable piece in `attackers` and the bit it stands on
+fn least_valuable_attacker(
+    pieces: &PieceArrangement,
+    attackers: Bitboard,
+) -> Option<(PieceType, Bitboard)> {
+    const PIECES: [NonKingPieceType; NonKingPieceType::COUNT] = [
+        NonKingPieceType::Pawn,
+        NonKingPieceType::Knight,
+        NonKingPieceType::Bishop,
+        NonKingPieceType::Rook,
+        NonKingPieceType::Queen,
+    ];
+
+    PIECES
+        .into_iter()
+        .map(|piece| {
+            (
+                piece.to_piece_type(),
+                attackers & pieces.mask_for_piece_type(piece),
+            )
+        })
+        .chain([(PieceType::King, attackers & pieces.kings())])
+        .find(|&(_, mask)| mask != 0)
+        .map(|(piece, mask)| (piece, mask & mask.wrapping_neg()))
+}
+
+/// Static exchange evaluation: the material `mv` wins for the side to move, in centipawns, if both
+/// sides keep recapturing on its destination with their least valuable piece for as long as that
+/// pays
+///
+/// Sliders behind the pieces that capture join in as those move away. Pins are not considered, and
+/// a move that captures nothing is scored by whether the piece survives on its new square.
+pub fn see(board: &AnyBoard, mv: Move) -> i32 {
+    if let MoveKind::Castle(_) = mv.kind() {
+        return 0;
+    }
+
+    let pieces = board.pieces();
+    let (from, to) = (mv.from(), mv.to());
+    let queens = pieces.mask_for_piece_type(NonKingPieceType::Queen);
+    let diagonal = pieces.mask_for_piece_type(NonKingPieceType::Bishop) | queens;
+    let orthogonal = pieces.mask_for_piece_type(NonKingPieceType::Rook) | queens;
+
+    let mut occupied = pieces.occupied() & !from.to_bit();
+    let mut gain = [0; MAX_EXCHANGE];
+    gain[0] = match mv.kind() {
+        MoveKind::EnPassant => {
+            occupied &= !Square::from_file_and_rank(to.file(), from.rank()).to_bit();
+            piece_value(PieceType::Pawn)
+        }
+        _ if mv.is_capture() => pieces.piece_type_on(to).map_or(0, piece_value),
+        _ => 0,
+    };
+    // The value of whatever now stands on `to`, which is what the next capture wins
+    let mut on_square = match mv.promotion() {
+        Some(promotion) => {
+            let value = piece_value(promotion.to_piece_type());
+            gain[0] += value - piece_value(PieceType::Pawn);
+            value
+        }
+        None => pieces.piece_type_on(from).map_or(0, piece_value),
+    };
+
+    let mut side = board.side_to_move().switch();
+    let mut attackers = pieces.attackers_to(to, occupied) & occupied;
+    let mut depth = 0;
+    while let Some((piece, bit)) =
+        least_valuable_attacker(pieces, attackers & pieces.mask_for_side(side))
+    {
+        // The king can only take last, when nothing can take it back
+        if piece == PieceType::King && attackers & pieces.mask_for_side(side.switch()) != 0 {
+            break;
+        }
+
+        depth += 1;
+        gain[depth] = on_square - gain[depth - 1];
+        on_square = piece_value(piece);
+        occupied &= !bit;
+        if matches!(
+            piece,
+            PieceType::Pawn | PieceType::Bishop | PieceType::Queen
+        ) {
+            attackers |= bishop_attacks(to, occupied) & diagonal;
+        }
+        if matches!(piece, PieceType::Rook | PieceType::Queen) {
+            attackers |= rook_attacks(to, occupied) & orthogonal;
+        }
+        attackers &= occupied;
+        side = side.switch();
+    }
+
+    // Unwind the exchange, each side stopping as soon as capturing again would lose material
+    while depth > 0 {
+        gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        depth -= 1;
+    }
+
+    gain[0]
+}
+
+/// Whether [`see`] of `mv` is at least `threshold`
+pub fn see_ge(board: &AnyBoard, mv: Move, threshold: i32) -> bool {
+    see(board, mv) >= threshold
+}
+
+#[cfg(test)]
+mod test {
+    use crate::any_board::AnyBoard;
+    use crate::see::{see, see_ge};
+    use test_case::test_case;
+
+    fn see_of(fen: &str, uci: &str) -> i32 {
+        let board = fen.parse::<AnyBoard>().unwrap();
+        let mv = board.legal_moves().find_uci(uci.parse().unwrap()).unwrap();
+        see(&board, mv)
+    }
+
+    #[test_case("4k3/8/8/3p4/8/8/8/3RK3 w - - 0 1", "d1d5" => 100; "undefended pawn")]
+    #[test_case("4k3/8/4p3/3p4/8/8/8/3RK3 w - - 0 1", "d1d5" => -400; "rook for a defended pawn")]
+    #[test_case("4k3/8/4p3/3p4/4P3/8/8/3RK3 w - - 0 1", "e4d5" => 100; "pawn takes pawn")]
+    #[test_case("3rk3/8/8/3p4/8/8/3Q4/3RK3 w - - 0 1", "d2d5" => -300; "queen for a defended pawn")]
+    #[test_case("3r3k/3r4/8/3p4/8/2N5/3R4/4K3 w - - 0 1", "c3d5" => -220; "rook behind a rook recaptures")]
+    #[test_case("3rk3/3r4/8/3p4/8/8/3Q4/3RK3 w - - 0 1", "d2d5" => -800; "outnumbered on the file")]
+    #[test_case("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5" => 100; "classic free pawn")]
+    #[test_case("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "d3e5" => -220; "classic knight trade")]
+    #[test_case("4k3/8/8/8/8/8/3q4/3K4 w - - 0 1", "d1d2" => 900; "king takes an undefended queen")]
+    #[test_case("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", "e2e3" => 0; "quiet move to a safe square")]
+    #[test_case("4k3/8/8/3p4/8/8/4P3/4K3 w - - 0 1", "e2e4" => -100; "pushing into a capture")]
+    #[test_case("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6" => 100; "en passant")]
+    #[test_case("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8q" => 1120; "capture promoting to a queen")]
+    #[test_case("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q" => -100; "promoting to be taken")]
+    fn exchanges(fen: &str, mv: &str) -> i32 {
+        see_of(fen, mv)
+    }
+
+    #[test]
+    fn see_ge_thresholds() {
+        let board = "4k3/8/4p3/3p4/4P3/8/8/3RK3 w - - 0 1"
+            .parse::<AnyBoard>()
+            .unwrap();
+        let mv = board
+            .legal_moves()
+            .find_uci("e4d5".parse().unwrap())
+            .unwrap();
+        assert!(see_ge(&board, mv, 0));
+        assert!(see_ge(&board, mv, 100));
+        assert!(!see_ge(&board, mv, 101));
+    }
+}
@@ -1,7 +1,10 @@
 use crate::bitboard::Bit;
 
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
 #[rustfmt::skip]
-#[derive( Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive( Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Square {
     A1, B1, C1, D1, E1, F1, G1, H1,
     A2, B2, C2, D2, E2, F2, G2, H2,
@@ -34,4 +37,85 @@ impl Square {
     pub const fn to_bit(self) -> Bit {
         1u64 << (self as u8)
     }
+
+    pub const fn from_file_and_rank(file: u8, rank: u8) -> Self {
+        debug_assert!(file < 8 && rank < 8);
+        SQUARES[(rank * 8 + file) as usize]
+    }
+
+    /// The zero based file, 0 for the a file
+    pub const fn file(self) -> u8 {
+        self as u8 % 8
+    }
+
+    /// The zero based rank, 0 for the first rank
+    pub const fn rank(self) -> u8 {
+        self as u8 / 8
+    }
+}
+
+/// A square name was not a file `a`-`h` followed by a rank `1`-`8`
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct ParseSquareError;
+
+impl Display for ParseSquareError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("expected a square like e4")
+    }
+}
+
+impl std::error::Error for ParseSquareError {}
+
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+                Ok(Self::from_file_and_rank(file - b'a', rank - b'1'))
+            }
+            _ => Err(ParseSquareError),
+        }
+    }
+}
+
+impl Display for Square {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            (b'a' + self.file()) as char,
+            (b'1' + self.rank()) as char
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::square::{Square, Square::*, SQUARES};
+    use test_case::test_case;
+
+    #[test_case("a1", A1)]
+    #[test_case("e4", E4)]
+    #[test_case("h8", H8)]
+    fn parse_square(name: &str, expected: Square) {
+        assert_eq!(name.parse::<Square>(), Ok(expected));
+    }
+
+    #[test_case(""; "empty")]
+    #[test_case("e"; "missing rank")]
+    #[test_case("i1"; "file out of range")]
+    #[test_case("a9"; "rank out of range")]
+    #[test_case("E4"; "uppercase file")]
+    #[test_case("e4 "; "trailing space")]
+    fn parse_invalid_square(name: &str) {
+        assert!(name.parse::<Square>().is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for square in SQUARES {
+            assert_eq!(square.to_string().parse::<Square>(), Ok(square));
+        }
+    }
 }
@@ -0,0 +1,151 @@
+use crate::chess_move::Move;
+
+use core::time::Duration;
+
+/// Moves assumed left in the game when the GUI does not say with `movestogo`
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// The most of the clock, in tenths, the soft limit takes
+const SOFT_SHARE: u32 = 5;
+/// The most of the clock, in tenths, the hard limit takes
+const HARD_SHARE: u32 = 8;
+/// How far past the soft limit an unstable search may run
+const HARD_FACTOR: u32 = 4;
+/// Score drops, in centipawns, past which the search takes more time
+const SCORE_DROP: i32 = 25;
+
+/// The time to spend on one move: stop after the first iteration past `soft`, and stop the
+/// search wherever it is at `hard`
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct TimeBudget {
+    pub soft: Duration,
+    pub hard: Duration,
+}
+
+impl TimeBudget {
+    /// Split `remaining` on the clock plus the `increment` over the moves to go, keeping
+    /// `overhead` back for the time lost talking to the GUI
+    pub fn new(
+        remaining: Duration,
+        increment: Duration,
+        moves_to_go: Option<u32>,
+        overhead: Duration,
+    ) -> Self {
+        let available = remaining
+            .saturating_sub(overhead)
+            .max(Duration::from_millis(1));
+        let moves_to_go = moves_to_go
+            .unwrap_or(DEFAULT_MOVES_TO_GO)
+            .clamp(1, DEFAULT_MOVES_TO_GO);
+        let hard_cap = available * HARD_SHARE / 10;
+        let soft = (available / moves_to_go + increment * 3 / 4).min(available * SOFT_SHARE / 10);
+
+        Self {
+            soft,
+            hard: (soft * HARD_FACTOR).min(hard_cap),
+        }
+    }
+}
+
+/// Decides after each iteration whether another is worth starting, spending more of the budget
+/// while the best move keeps changing or the score is falling
+#[derive(Clone, Debug)]
+pub struct TimeManager {
+    budget: TimeBudget,
+    best_move: Option<Move>,
+    score: Option<i32>,
+    /// Grows with every change of best move and decays with every iteration that keeps it
+    instability: f64,
+}
+
+impl TimeManager {
+    pub const fn new(budget: TimeBudget) -> Self {
+        Self {
+            budget,
+            best_move: None,
+            score: None,
+            instability: 0.0,
+        }
+    }
+
+    /// Record a completed iteration's best move and score, in centipawns, and decide whether to
+    /// stop with `elapsed` spent so far
+    pub fn should_stop(&mut self, elapsed: Duration, best_move: Option<Move>, score: i32) -> bool {
+        self.instability *= 0.5;
+        if self.best_move.is_some() && self.best_move != best_move {
+            self.instability += 1.0;
+        }
+        let drop = self.score.map_or(0, |previous| previous - score);
+        self.best_move = best_move;
+        self.score = Some(score);
+
+        let mut scale = 1.0 + 0.5 * self.instability;
+        if drop > SCORE_DROP {
+            scale *= 1.0 + (drop as f64 / 100.0).min(1.0);
+        }
+        let soft = self.budget.soft.mul_f64(scale).min(self.budget.hard);
+
+        elapsed >= soft
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::chess_move::{Move, MoveKind};
+    use crate::square::Square;
+    use crate::time_management::{TimeBudget, TimeManager};
+    use core::time::Duration;
+
+    const fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn budgets_fit_on_the_clock() {
+        // Sudden death spreads the clock over the moves likely left
+        let budget = TimeBudget::new(ms(60_000), ms(0), None, ms(10));
+        assert_eq!(budget.soft, ms(59_990) / 30);
+        assert_eq!(budget.hard, budget.soft * 4);
+
+        // Most of the increment is spent as it comes in
+        let budget = TimeBudget::new(ms(10_000), ms(1_000), None, ms(0));
+        assert_eq!(budget.soft, ms(10_000) / 30 + ms(750));
+
+        // The last move before the time control still leaves time on the clock
+        let budget = TimeBudget::new(ms(5_000), ms(0), Some(1), ms(0));
+        assert_eq!(budget.soft, ms(2_500));
+        assert_eq!(budget.hard, ms(4_000));
+
+        // However little is left, and whatever the overhead
+        for (remaining, overhead) in [(50, 10), (5, 10), (0, 0)] {
+            let budget = TimeBudget::new(ms(remaining), ms(2_000), None, ms(overhead));
+            assert!(budget.soft <= budget.hard);
+            assert!(budget.hard < ms(remaining.max(1)));
+        }
+    }
+
+    #[test]
+    fn instability_takes_more_time() {
+        let budget = TimeBudget {
+            soft: ms(100),
+            hard: ms(400),
+        };
+        let e4 = Some(Move::new(Square::E2, Square::E4, MoveKind::DoublePawnPush));
+        let d4 = Some(Move::new(Square::D2, Square::D4, MoveKind::DoublePawnPush));
+
+        let mut stable = TimeManager::new(budget);
+        assert!(!stable.should_stop(ms(50), e4, 20));
+        assert!(stable.should_stop(ms(110), e4, 20));
+
+        let mut changing = TimeManager::new(budget);
+        assert!(!changing.should_stop(ms(50), e4, 20));
+        assert!(!changing.should_stop(ms(110), d4, 20));
+        assert!(!changing.should_stop(ms(130), e4, 20));
+        assert!(changing.should_stop(ms(200), e4, 20));
+
+        let mut falling = TimeManager::new(budget);
+        assert!(!falling.should_stop(ms(50), e4, 20));
+        assert!(!falling.should_stop(ms(150), e4, -80));
+        // Once the score settles the usual budget applies again
+        assert!(falling.should_stop(ms(160), e4, -80));
+    }
+}
@@ -0,0 +1,298 @@
+use crate::chess_move::Move;
+
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+/// Megabytes used by a table before the `Hash` option says otherwise
+pub const DEFAULT_HASH_MB: usize = 16;
+/// Entries sharing one bucket, sized so a bucket fills a cache line
+const BUCKET_SIZE: usize = 4;
+/// Searches are told apart by an age this many bits wide
+const AGE_BITS: u32 = 6;
+const AGE_MASK: u8 = (1 << AGE_BITS) - 1;
+
+/// How the stored score relates to the true score of the position
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Bound {
+    /// The score is exact
+    Exact = 1,
+    /// The search failed high, the true score is at least this
+    Lower = 2,
+    /// The search failed low, the true score is at most this
+    Upper = 3,
+}
+
+/// What one search learnt about a position
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Entry {
+    pub best_move: Option<Move>,
+    pub depth: u8,
+    pub score: i16,
+    pub bound: Bound,
+}
+
+impl Entry {
+    /// Pack into 64 bits: the move, score, depth, bound and the age of the search storing it
+    const fn to_bits(self, age: u8) -> u64 {
+        let best_move = match self.best_move {
+            Some(mv) => mv.to_bits(),
+            None => 0,
+        };
+        best_move as u64
+            | (self.score as u16 as u64) << 16
+            | (self.depth as u64) << 32
+            | (self.bound as u64) << 40
+            | (age as u64) << 42
+    }
+
+    /// Unpack an entry packed by [`Entry::to_bits`], `None` for an empty slot
+    const fn from_bits(bits: u64) -> Option<Self> {
+        let bound = match (bits >> 40) & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        // No move is stored as zero, which unpacks to None as a1a1 is no move
+        let best_move = Move::from_bits(bits as u16);
+
+        Some(Self {
+            best_move,
+            score: (bits >> 16) as u16 as i16,
+            depth: (bits >> 32) as u8,
+            bound,
+        })
+    }
+}
+
+const fn age_of(bits: u64) -> u8 {
+    (bits >> 42) as u8 & AGE_MASK
+}
+
+/// One entry, its key stored XORed with its data so a slot torn by two threads writing at once
+/// fails verification instead of handing back another position's data
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+#[derive(Default)]
+#[repr(align(64))]
+struct Bucket([Slot; BUCKET_SIZE]);
+
+/// A fixed size cache of search results keyed by Zobrist key, shared between search threads
+/// without locks
+pub struct TranspositionTable {
+    buckets: Box<[Bucket]>,
+    /// Bumped by every new search, so stale entries are replaced first
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    /// A table of at most `megabytes`, rounded down to a power of two number of buckets
+    pub fn new(megabytes: usize) -> Self {
+        let buckets = (megabytes.max(1) << 20) / size_of::<Bucket>();
+        let buckets = 1 << buckets.ilog2();
+
+        Self {
+            buckets: (0..buckets).map(|_| Bucket::default()).collect(),
+            age: AtomicU8::new(0),
+        }
+    }
+
+    /// Forget every entry
+    pub fn clear(&self) {
+        for slot in self.buckets.iter().flat_map(|bucket| &bucket.0) {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    /// Mark the start of a new search, whose entries are kept in favour of the earlier ones
+    pub fn new_search(&self) {
+        let age = self.age.load(Ordering::Relaxed);
+        self.age.store((age + 1) & AGE_MASK, Ordering::Relaxed);
+    }
+
+    fn bucket(&self, key: u64) -> &Bucket {
+        &self.buckets[key as usize & (self.buckets.len() - 1)]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.bucket(key).0.iter().find_map(|slot| {
+            let data = slot.data.load(Ordering::Relaxed);
+            if slot.key.load(Ordering::Relaxed) ^ data == key {
+                Entry::from_bits(data)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Store `entry` for `key`, over the same position if it is already stored, else over the
+    /// least useful entry in its bucket: the shallowest, counting entries from earlier searches
+    /// as shallower the older they are
+    pub fn store(&self, key: u64, mut entry: Entry) {
+        let age = self.age.load(Ordering::Relaxed);
+        let bucket = &self.bucket(key).0;
+        let worth = |slot: &Slot| {
+            let data = slot.data.load(Ordering::Relaxed);
+            if slot.key.load(Ordering::Relaxed) ^ data == key {
+                return i32::MIN;
+            }
+            match Entry::from_bits(data) {
+                Some(stored) => {
+                    let searches_ago = age.wrapping_sub(age_of(data)) & AGE_MASK;
+                    stored.depth as i32 - 8 * searches_ago as i32
+                }
+                None => i32::MIN + 1,
+            }
+        };
+        let slot = bucket
+            .iter()
+            .min_by_key(|slot| worth(slot))
+            .expect("buckets are never empty");
+
+        // Keep the best move of a shallower search of this position rather than forget it
+        let data = slot.data.load(Ordering::Relaxed);
+        if entry.best_move.is_none() && slot.key.load(Ordering::Relaxed) ^ data == key {
+            entry.best_move = Entry::from_bits(data).and_then(|stored| stored.best_move);
+        }
+
+        let data = entry.to_bits(age);
+        slot.key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    /// How full the table is with entries from the current search, in permille as in UCI
+    /// `hashfull`, estimated from the first thousand entries
+    pub fn hashfull(&self) -> u16 {
+        let age = self.age.load(Ordering::Relaxed);
+        let sample = self.buckets.iter().flat_map(|bucket| &bucket.0).take(1000);
+        let (mut used, mut total) = (0usize, 0usize);
+        for slot in sample {
+            let data = slot.data.load(Ordering::Relaxed);
+            total += 1;
+            if Entry::from_bits(data).is_some() && age_of(data) == age {
+                used += 1;
+            }
+        }
+
+        (used * 1000 / total) as u16
+    }
+
+    /// The number of entries the table holds
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::chess_move::{Move, MoveKind};
+    use crate::square::Square;
+    use crate::transposition_table::{Bound, Entry, TranspositionTable};
+
+    fn entry(depth: u8, score: i16) -> Entry {
+        Entry {
+            best_move: Some(Move::new(Square::E2, Square::E4, MoveKind::DoublePawnPush)),
+            depth,
+            score,
+            bound: Bound::Exact,
+        }
+    }
+
+    #[test]
+    fn sizes_are_powers_of_two() {
+        assert_eq!(TranspositionTable::new(1).capacity(), 1 << 16);
+        assert_eq!(TranspositionTable::new(3).capacity(), 1 << 17);
+    }
+
+    #[test]
+    fn stores_and_probes() {
+        let table = TranspositionTable::new(1);
+        let key = 0x463b96181691fc9c;
+        assert_eq!(table.probe(key), None);
+
+        let stored = Entry {
+            bound: Bound::Lower,
+            ..entry(7, -29_990)
+        };
+        table.store(key, stored);
+        assert_eq!(table.probe(key), Some(stored));
+        // Another key in the same bucket is a miss
+        assert_eq!(table.probe(key ^ 1 << 63), None);
+
+        let no_move = Entry {
+            best_move: None,
+            ..entry(9, 15)
+        };
+        table.store(key, no_move);
+        assert_eq!(
+            table.probe(key),
+            Some(Entry {
+                best_move: stored.best_move,
+                ..no_move
+            })
+        );
+
+        table.clear();
+        assert_eq!(table.probe(key), None);
+    }
+
+    #[test]
+    fn replaces_the_shallowest_and_oldest() {
+        let table = TranspositionTable::new(1);
+        let buckets = (table.capacity() / 4) as u64;
+        let key = |n: u64| 5 + n * buckets;
+        for n in 0..4 {
+            table.store(key(n), entry(10 + n as u8, 0));
+        }
+        table.store(key(4), entry(1, 0));
+        assert_eq!(table.probe(key(0)), None);
+        assert!((1..5).all(|n| table.probe(key(n)).is_some()));
+
+        // Two searches on even the deep entries give way to shallow new ones
+        table.new_search();
+        table.new_search();
+        table.store(key(5), entry(1, 0));
+        table.store(key(6), entry(1, 0));
+        assert_eq!(table.probe(key(4)), None);
+        assert_eq!(table.probe(key(1)), None);
+        assert!([2, 3, 5, 6].iter().all(|&n| table.probe(key(n)).is_some()));
+    }
+
+    #[test]
+    fn torn_writes_fail_verification() {
+        let table = TranspositionTable::new(1);
+        let key = 42;
+        table.store(key, entry(3, 50));
+        // As if another thread wrote its data between this entry's key and data
+        let slot = &table.bucket(key).0[0];
+        slot.data.store(
+            entry(5, -50).to_bits(0),
+            core::sync::atomic::Ordering::Relaxed,
+        );
+        assert_eq!(table.probe(key), None);
+    }
+
+    #[test]
+    fn hashfull_counts_the_current_search() {
+        let table = TranspositionTable::new(1);
+        assert_eq!(table.hashfull(), 0);
+        for key in 0..table.capacity() as u64 / 4 {
+            table.store(key, entry(1, 0));
+        }
+        assert_eq!(table.hashfull(), 250);
+        table.new_search();
+        assert_eq!(table.hashfull(), 0);
+    }
+}
@@ -0,0 +1,390 @@
+use crate::any_board::{AnyBoard, ParseBoardError};
+use crate::evaluation::{trace_with, Tapered, Weights};
+use crate::piece_type::PieceType;
+
+use core::fmt::{self, Display, Formatter, Write};
+use core::num::NonZeroUsize;
+use std::thread;
+
+/// A position and the result of the game it was taken from, 1 for a white win, 0.5 for a draw and
+/// 0 for a black win
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LabelledPosition {
+    pub board: AnyBoard,
+    pub result: f64,
+}
+
+/// Why a line of a dataset could not be read
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum DatasetError {
+    /// The line has no result after the FEN
+    MissingResult {
+        line: usize,
+    },
+    InvalidResult {
+        line: usize,
+        result: String,
+    },
+    InvalidFen {
+        line: usize,
+        error: ParseBoardError,
+    },
+}
+
+impl Display for DatasetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::MissingResult { line } => write!(f, "line {line}: missing game result"),
+            DatasetError::InvalidResult { line, result } => {
+                write!(f, "line {line}: invalid game result '{result}'")
+            }
+            DatasetError::InvalidFen { line, error } => write!(f, "line {line}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for DatasetError {}
+
+fn parse_result(result: &str) -> Option<f64> {
+    let result = result.trim_matches(|c: char| "[]\"';".contains(c));
+    match result {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" | "½-½" => Some(0.5),
+        _ => result
+            .parse::<f64>()
+            .ok()
+            .filter(|result| (0.0..=1.0).contains(result)),
+    }
+}
+
+/// Read one position per line, a FEN followed by the result as `1-0`, `1/2-1/2`, `0-1` or a
+/// number from 0 to 1, optionally quoted or bracketed as in `[0.5]` or `c9 "1-0";`
+///
+/// The clocks may be left out of the FEN. Blank lines and lines starting with `#` are skipped.
+pub fn parse_dataset(text: &str) -> Result<Vec<LabelledPosition>, DatasetError> {
+    let mut positions = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_ascii_whitespace().collect();
+        let (&result, fen) = fields.split_last().expect("the line is not blank");
+        if fen.len() < 4 {
+            return Err(DatasetError::MissingResult { line: line_number });
+        }
+        let result = parse_result(result).ok_or_else(|| DatasetError::InvalidResult {
+            line: line_number,
+            result: result.to_string(),
+        })?;
+
+        // Extended position descriptions put operations like `c9` after the four FEN fields
+        let fen = match fen.get(4..6) {
+            Some(clocks) if clocks.iter().all(|field| field.parse::<u16>().is_ok()) => {
+                fen[..6].join(" ")
+            }
+            _ => format!("{} 0 1", fen[..4].join(" ")),
+        };
+        let board = fen.parse().map_err(|error| DatasetError::InvalidFen {
+            line: line_number,
+            error,
+        })?;
+        positions.push(LabelledPosition { board, result });
+    }
+
+    Ok(positions)
+}
+
+/// Every weight in a fixed order, as the tuner changes them one by one
+fn for_each_weight(weights: &mut Weights, mut f: impl FnMut(&mut Tapered)) {
+    weights.material.iter_mut().for_each(&mut f);
+    weights.piece_squares.iter_mut().flatten().for_each(&mut f);
+    weights.mobility.iter_mut().for_each(&mut f);
+    weights.passed_pawn.iter_mut().for_each(&mut f);
+    f(&mut weights.isolated_pawn);
+    f(&mut weights.doubled_pawn);
+    f(&mut weights.backward_pawn);
+    weights.king_attack.iter_mut().for_each(&mut f);
+    f(&mut weights.bishop_pair);
+    f(&mut weights.rook_open_file);
+    f(&mut weights.rook_semi_open_file);
+}
+
+/// Flatten `weights` into middlegame and endgame pairs
+pub fn to_parameters(weights: &Weights) -> Vec<i32> {
+    let mut parameters = Vec::new();
+    for_each_weight(&mut weights.clone(), |weight| {
+        parameters.extend([weight.mg, weight.eg]);
+    });
+    parameters
+}
+
+/// The inverse of [`to_parameters`], filling in `template` with `parameters`
+pub fn from_parameters(template: &Weights, parameters: &[i32]) -> Weights {
+    let mut weights = template.clone();
+    let mut parameters = parameters.iter();
+    for_each_weight(&mut weights, |weight| {
+        weight.mg = *parameters.next().expect("too few parameters");
+        weight.eg = *parameters.next().expect("too few parameters");
+    });
+    assert!(parameters.next().is_none(), "too many parameters");
+    weights
+}
+
+/// The expected score for white of a position evaluated at `centipawns`, scaled by `k`
+pub fn win_probability(centipawns: i32, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * centipawns as f64 / 400.0))
+}
+
+fn threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// The mean squared error of the evaluation's predicted results against the actual results
+pub fn loss(positions: &[LabelledPosition], weights: &Weights, k: f64) -> f64 {
+    if positions.is_empty() {
+        return 0.0;
+    }
+
+    let chunk = positions.len().div_ceil(threads());
+    let total: f64 = thread::scope(|scope| {
+        let workers: Vec<_> = positions
+            .chunks(chunk)
+            .map(|positions| {
+                scope.spawn(move || {
+                    positions
+                        .iter()
+                        .map(|position| {
+                            let score = trace_with(&position.board, weights).white_score();
+                            (position.result - win_probability(score, k)).powi(2)
+                        })
+                        .sum::<f64>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("loss worker panicked"))
+            .sum()
+    });
+
+    total / positions.len() as f64
+}
+
+/// The scaling constant for [`win_probability`] that best fits `weights` to `positions`, found by
+/// narrowing in on the minimum a digit at a time
+pub fn optimal_k(positions: &[LabelledPosition], weights: &Weights) -> f64 {
+    let (mut k, mut step) = (1.0, 0.1);
+    for _ in 0..4 {
+        let mut best = (loss(positions, weights, k), k);
+        let mut candidate = (k - 10.0 * step).max(step);
+        while candidate <= k + 10.0 * step {
+            let error = loss(positions, weights, candidate);
+            if error < best.0 {
+                best = (error, candidate);
+            }
+            candidate += step;
+        }
+        k = best.1;
+        step /= 10.0;
+    }
+    k
+}
+
+/// Tune `weights` to `positions` by local search: nudge each parameter up or down by one and keep
+/// whichever lowers the loss, until a pass over every parameter changes nothing or `passes` run out
+///
+/// `on_pass` is told the number and loss after each pass, to report progress or save the weights.
+pub fn tune(
+    positions: &[LabelledPosition],
+    weights: &Weights,
+    k: f64,
+    passes: usize,
+    mut on_pass: impl FnMut(usize, f64, &Weights),
+) -> Weights {
+    let mut parameters = to_parameters(weights);
+    let mut best = loss(positions, weights, k);
+
+    for pass in 1..=passes {
+        let mut improved = false;
+        for index in 0..parameters.len() {
+            for delta in [1, -1] {
+                parameters[index] += delta;
+                let error = loss(positions, &from_parameters(weights, &parameters), k);
+                if error < best {
+                    best = error;
+                    improved = true;
+                    break;
+                }
+                parameters[index] -= delta;
+            }
+        }
+
+        on_pass(pass, best, &from_parameters(weights, &parameters));
+        if !improved {
+            break;
+        }
+    }
+
+    from_parameters(weights, &parameters)
+}
+
+const PIECE_NAMES: [&str; PieceType::COUNT] = ["Pawn", "Knight", "Bishop", "Rook", "Queen", "King"];
+
+fn write_tapered(source: &mut String, weight: Tapered) {
+    write!(source, "Tapered::new({}, {})", weight.mg, weight.eg).unwrap();
+}
+
+fn write_tapered_array(source: &mut String, name: &str, weights: &[Tapered]) {
+    writeln!(source, "    {name}: [").unwrap();
+    for &weight in weights {
+        source.push_str("        ");
+        write_tapered(source, weight);
+        source.push_str(",\n");
+    }
+    source.push_str("    ],\n");
+}
+
+fn write_table(source: &mut String, table: impl Iterator<Item = i32>) {
+    source.push_str("            [");
+    for (square, value) in table.enumerate() {
+        if square % 8 == 0 {
+            source.push_str("\n               ");
+        }
+        write!(source, " {value:>4},").unwrap();
+    }
+    source.push_str("\n            ],\n");
+}
+
+/// `weights` as the Rust source of the [`WEIGHTS`](crate::evaluation::WEIGHTS) constant, ready to
+/// paste over it in `evaluation.rs`
+pub fn to_rust_source(weights: &Weights) -> String {
+    let mut source = String::from("pub const WEIGHTS: Weights = Weights {\n");
+    write_tapered_array(&mut source, "material", &weights.material);
+
+    source.push_str("    piece_squares: [\n");
+    for (name, table) in PIECE_NAMES.into_iter().zip(&weights.piece_squares) {
+        writeln!(source, "        // {name}").unwrap();
+        source.push_str("        piece_square_table(\n");
+        write_table(&mut source, table.iter().map(|weight| weight.mg));
+        write_table(&mut source, table.iter().map(|weight| weight.eg));
+        source.push_str("        ),\n");
+    }
+    source.push_str("    ],\n");
+
+    write_tapered_array(&mut source, "mobility", &weights.mobility);
+    write_tapered_array(&mut source, "passed_pawn", &weights.passed_pawn);
+    for (name, weight) in [
+        ("isolated_pawn", weights.isolated_pawn),
+        ("doubled_pawn", weights.doubled_pawn),
+        ("backward_pawn", weights.backward_pawn),
+    ] {
+        write!(source, "    {name}: ").unwrap();
+        write_tapered(&mut source, weight);
+        source.push_str(",\n");
+    }
+    write_tapered_array(&mut source, "king_attack", &weights.king_attack);
+    for (name, weight) in [
+        ("bishop_pair", weights.bishop_pair),
+        ("rook_open_file", weights.rook_open_file),
+        ("rook_semi_open_file", weights.rook_semi_open_file),
+    ] {
+        write!(source, "    {name}: ").unwrap();
+        write_tapered(&mut source, weight);
+        source.push_str(",\n");
+    }
+    source.push_str("};\n");
+    source
+}
+
+#[cfg(test)]
+mod test {
+    use crate::any_board::AnyBoard;
+    use crate::evaluation::{Tapered, WEIGHTS};
+    use crate::tuning::{
+        from_parameters, loss, parse_dataset, to_parameters, to_rust_source, tune, win_probability,
+        DatasetError, LabelledPosition,
+    };
+    use test_case::test_case;
+
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - 0 1 1-0" => 1.0; "pgn result")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - 0 1 [0.5]" => 0.5; "bracketed")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - c9 \"0-1\";" => 0.0; "epd")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 b - - 1/2-1/2" => 0.5; "without clocks")]
+    fn parses_results(line: &str) -> f64 {
+        parse_dataset(line).unwrap()[0].result
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        let dataset =
+            "# comment\n\n4k3/8/8/8/8/8/8/4K3 w - - 0 1 1-0\n4k3/8/8/8/8/8/8/4K3 w - - 0 1 2-0\n";
+        assert_eq!(
+            parse_dataset(dataset),
+            Err(DatasetError::InvalidResult {
+                line: 4,
+                result: "2-0".to_string()
+            })
+        );
+        assert_eq!(
+            parse_dataset("1-0"),
+            Err(DatasetError::MissingResult { line: 1 })
+        );
+        assert!(matches!(
+            parse_dataset("4k3/8/8/8/8/8/8/4K3 x - - 0 1 1-0"),
+            Err(DatasetError::InvalidFen { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn parameters_round_trip() {
+        let mut parameters = to_parameters(&WEIGHTS);
+        assert_eq!(from_parameters(&WEIGHTS, &parameters), WEIGHTS);
+
+        parameters[0] += 1;
+        let changed = from_parameters(&WEIGHTS, &parameters);
+        assert_eq!(
+            changed.material[0],
+            WEIGHTS.material[0] + Tapered::new(1, 0)
+        );
+    }
+
+    #[test]
+    fn win_probability_is_even_at_zero() {
+        assert_eq!(win_probability(0, 1.0), 0.5);
+        assert!(win_probability(400, 1.0) > 0.9);
+        assert!(win_probability(-400, 1.0) < 0.1);
+    }
+
+    #[test]
+    fn tuning_lowers_the_loss() {
+        let position = |fen: &str, result| LabelledPosition {
+            board: fen.parse::<AnyBoard>().unwrap(),
+            result,
+        };
+        // Extra knights lose here, which only a lower knight value can fit
+        let positions = [
+            position("4k3/8/8/8/8/8/8/1N2K1N1 w - - 0 1", 0.5),
+            position("1n2k3/8/8/8/8/8/8/4K3 w - - 0 1", 0.5),
+        ];
+        let before = loss(&positions, &WEIGHTS, 1.0);
+        let mut passes = 0;
+        let tuned = tune(&positions, &WEIGHTS, 1.0, 2, |_, _, _| passes += 1);
+        assert_eq!(passes, 2);
+        assert!(loss(&positions, &tuned, 1.0) < before);
+        assert!(tuned.material[1].eg < WEIGHTS.material[1].eg);
+    }
+
+    #[test]
+    fn writes_the_weights_constant() {
+        let source = to_rust_source(&WEIGHTS);
+        assert!(source.starts_with("pub const WEIGHTS: Weights = Weights {\n"));
+        assert!(source.contains("        Tapered::new(82, 94),\n"));
+        assert!(source.contains("    bishop_pair: Tapered::new(30, 50),\n"));
+        assert_eq!(source.matches("piece_square_table(").count(), 6);
+        assert!(source.ends_with("};\n"));
+    }
+}
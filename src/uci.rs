@@ -0,0 +1,727 @@
+use crate::any_board::{AnyBoard, ParseBoardError};
+use crate::castle_direction::CastlingRooks;
+use crate::chess_move::{Move, ParseUciMoveError, UciMove};
+use crate::evaluation::{evaluate, trace};
+use crate::fen::FenError;
+use crate::nnue::{Network, NetworkError};
+use crate::perft::print_divide;
+use crate::player::Player;
+use crate::position_builder::PositionError;
+use crate::search::{search, Limits, SearchInfo};
+use crate::time_management::TimeBudget;
+use crate::transposition_table::{TranspositionTable, DEFAULT_HASH_MB};
+
+use core::fmt::{self, Display, Formatter};
+use core::time::Duration;
+use std::io::BufRead;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// The parameters of a `go` command, every limit is optional
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Go {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+    /// Search until `stop`, ignoring every other limit
+    pub infinite: bool,
+    /// Not part of UCI, count the leaves this deep with [`print_divide`] instead of searching
+    pub perft: Option<u32>,
+}
+
+impl Go {
+    /// What to stop the search at with `side` to move, keeping `overhead` back from every time
+    /// limit for the delay between the engine and the clock
+    pub fn limits(&self, side: Player, overhead: Duration) -> Limits {
+        let (clock, increment) = match side {
+            Player::White => (self.wtime, self.winc),
+            Player::Black => (self.btime, self.binc),
+        };
+        let time = match (self.movetime, clock) {
+            (Some(movetime), _) => {
+                let time = movetime
+                    .saturating_sub(overhead)
+                    .max(Duration::from_millis(1));
+                Some(TimeBudget {
+                    soft: time,
+                    hard: time,
+                })
+            }
+            (None, Some(clock)) => Some(TimeBudget::new(
+                clock,
+                increment.unwrap_or_default(),
+                self.movestogo,
+                overhead,
+            )),
+            (None, None) => None,
+        };
+
+        if self.infinite {
+            Limits::default()
+        } else {
+            Limits {
+                depth: self.depth,
+                nodes: self.nodes,
+                time,
+            }
+        }
+    }
+}
+
+/// One line of input from the GUI
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Command {
+    Uci,
+    IsReady,
+    UciNewGame,
+    /// `position startpos|fen <fen> [moves <move>...]`, the moves not yet checked for legality
+    Position {
+        board: Box<AnyBoard>,
+        moves: Vec<UciMove>,
+    },
+    Go(Go),
+    Stop,
+    Quit,
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
+    /// `eval [trace]`, the static evaluation of the current position, broken down by term with
+    /// `trace`
+    Eval {
+        trace: bool,
+    },
+}
+
+/// Why a line of input could not be understood
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum UciError {
+    UnknownCommand(String),
+    /// `position` without `startpos` or `fen`
+    MissingPosition,
+    /// Something other than `moves` after `startpos`
+    ExpectedMoves(String),
+    InvalidFen(FenError),
+    /// A FEN that parses but describes a position no game could reach
+    InvalidPosition(PositionError),
+    InvalidMove(ParseUciMoveError),
+    IllegalMove(UciMove),
+    /// A `go` limit or option value that is not a number or out of range
+    InvalidValue(&'static str),
+    /// A check option value that is neither `true` nor `false`
+    InvalidBool(&'static str),
+    /// `setoption` without `name`
+    MissingOptionName,
+    UnknownOption(String),
+    InvalidNetwork(NetworkError),
+    /// `Use NNUE` before an `EvalFile` was loaded
+    MissingNetwork,
+}
+
+impl Display for UciError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            UciError::UnknownCommand(command) => write!(f, "unknown command '{command}'"),
+            UciError::MissingPosition => f.write_str("expected startpos or fen after position"),
+            UciError::ExpectedMoves(token) => write!(f, "expected moves, found '{token}'"),
+            UciError::InvalidFen(err) => Display::fmt(err, f),
+            UciError::InvalidPosition(err) => Display::fmt(err, f),
+            UciError::InvalidMove(err) => Display::fmt(err, f),
+            UciError::IllegalMove(mv) => write!(f, "illegal move {mv}"),
+            UciError::InvalidValue(name) => write!(f, "expected a number after {name}"),
+            UciError::MissingOptionName => f.write_str("expected setoption name <name>"),
+            UciError::InvalidBool(name) => write!(f, "expected true or false after {name}"),
+            UciError::UnknownOption(name) => write!(f, "unknown option '{name}'"),
+            UciError::InvalidNetwork(err) => Display::fmt(err, f),
+            UciError::MissingNetwork => f.write_str("set EvalFile before Use NNUE"),
+        }
+    }
+}
+
+impl std::error::Error for UciError {}
+
+impl From<NetworkError> for UciError {
+    fn from(err: NetworkError) -> Self {
+        UciError::InvalidNetwork(err)
+    }
+}
+
+impl From<ParseBoardError> for UciError {
+    fn from(err: ParseBoardError) -> Self {
+        match err {
+            ParseBoardError::Fen(err) => UciError::InvalidFen(err),
+            ParseBoardError::Position(err) => UciError::InvalidPosition(err),
+        }
+    }
+}
+
+impl From<ParseUciMoveError> for UciError {
+    fn from(err: ParseUciMoveError) -> Self {
+        UciError::InvalidMove(err)
+    }
+}
+
+fn parse_position<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Result<Command, UciError> {
+    let board = match tokens.next() {
+        Some("startpos") => {
+            // Consume the `moves` keyword, as the take_while below does after a FEN
+            match tokens.next() {
+                None | Some("moves") => AnyBoard::default(),
+                Some(token) => return Err(UciError::ExpectedMoves(token.to_string())),
+            }
+        }
+        Some("fen") => {
+            let fen: Vec<_> = tokens
+                .by_ref()
+                .take_while(|&token| token != "moves")
+                .collect();
+            fen.join(" ").parse::<AnyBoard>()?
+        }
+        _ => return Err(UciError::MissingPosition),
+    };
+    let moves = tokens.map(str::parse).collect::<Result<_, _>>()?;
+
+    Ok(Command::Position {
+        board: Box::new(board),
+        moves,
+    })
+}
+
+fn parse_go<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Result<Command, UciError> {
+    let mut go = Go::default();
+    while let Some(token) = tokens.next() {
+        let mut number = |name| {
+            tokens
+                .next()
+                .and_then(|value| value.parse::<u64>().ok())
+                .ok_or(UciError::InvalidValue(name))
+        };
+        let millis = |ms: u64| Some(Duration::from_millis(ms));
+        let narrow =
+            |value: u64, name| u32::try_from(value).map_err(|_| UciError::InvalidValue(name));
+        match token {
+            "depth" => go.depth = Some(narrow(number("depth")?, "depth")?),
+            "nodes" => go.nodes = Some(number("nodes")?),
+            "movetime" => go.movetime = millis(number("movetime")?),
+            "wtime" => go.wtime = millis(number("wtime")?),
+            "btime" => go.btime = millis(number("btime")?),
+            "winc" => go.winc = millis(number("winc")?),
+            "binc" => go.binc = millis(number("binc")?),
+            "movestogo" => go.movestogo = Some(narrow(number("movestogo")?, "movestogo")?),
+            "infinite" => go.infinite = true,
+            "perft" => go.perft = Some(narrow(number("perft")?, "perft")?),
+            // UCI asks engines to skip what they do not understand, such as ponder
+            _ => {}
+        }
+    }
+
+    Ok(Command::Go(go))
+}
+
+fn parse_setoption<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Result<Command, UciError> {
+    if tokens.next() != Some("name") {
+        return Err(UciError::MissingOptionName);
+    }
+    // Option names and values may both contain spaces
+    let name: Vec<_> = tokens
+        .by_ref()
+        .take_while(|&token| token != "value")
+        .collect();
+    let value: Vec<_> = tokens.collect();
+    if name.is_empty() {
+        return Err(UciError::MissingOptionName);
+    }
+
+    Ok(Command::SetOption {
+        name: name.join(" "),
+        value: (!value.is_empty()).then(|| value.join(" ")),
+    })
+}
+
+impl Command {
+    /// Parse one line of input, `None` for a blank line
+    pub fn parse(line: &str) -> Option<Result<Self, UciError>> {
+        let mut tokens = line.split_ascii_whitespace();
+        let command = match tokens.next()? {
+            "uci" => Ok(Command::Uci),
+            "isready" => Ok(Command::IsReady),
+            "ucinewgame" => Ok(Command::UciNewGame),
+            "position" => parse_position(tokens),
+            "go" => parse_go(tokens),
+            "stop" => Ok(Command::Stop),
+            "quit" => Ok(Command::Quit),
+            "setoption" => parse_setoption(tokens),
+            "eval" => Ok(Command::Eval {
+                trace: tokens.next() == Some("trace"),
+            }),
+            command => Err(UciError::UnknownCommand(command.to_string())),
+        };
+
+        Some(command)
+    }
+}
+
+/// Play `moves` from `board`, failing on the first one that is not legal
+///
+/// With `chess960` castles are written as the king taking its own rook.
+fn play(board: AnyBoard, moves: &[UciMove], chess960: bool) -> Result<AnyBoard, UciError> {
+    moves.iter().try_fold(board, |board, &uci| {
+        let legal_moves = board.legal_moves();
+        let mv = if chess960 {
+            legal_moves.find_uci_chess960(uci, board.castling_rooks())
+        } else {
+            legal_moves.find_uci(uci)
+        };
+        Ok(board.make_move(mv.ok_or(UciError::IllegalMove(uci))?))
+    })
+}
+
+/// How `mv` is written to the GUI, in Chess960 notation when given the castling rooks
+fn to_uci(mv: Move, chess960: Option<CastlingRooks>) -> UciMove {
+    match chess960 {
+        Some(castling_rooks) => mv.to_uci_chess960(castling_rooks),
+        None => mv.to_uci(),
+    }
+}
+
+fn print_info(info: &SearchInfo, chess960: Option<CastlingRooks>) {
+    let pv = info
+        .pv
+        .iter()
+        .map(|&mv| to_uci(mv, chess960).to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    println!(
+        "info depth {} seldepth {} score {} nodes {} nps {} hashfull {} time {} pv {pv}",
+        info.depth,
+        info.seldepth,
+        info.score,
+        info.nodes,
+        info.nps(),
+        info.hashfull,
+        info.time.as_millis(),
+    );
+}
+
+/// The largest `Hash` accepted, in megabytes
+const MAX_HASH_MB: usize = 1 << 16;
+/// The most search threads accepted by `Threads`
+const MAX_THREADS: usize = 1024;
+/// Kept back from every time limit for the delay between the engine and the clock
+const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(10);
+/// The largest `Move Overhead` accepted, in milliseconds
+const MAX_MOVE_OVERHEAD_MS: usize = 5000;
+
+/// The engine state between commands, with at most one search running in the background
+pub struct Uci {
+    board: AnyBoard,
+    table: Arc<TranspositionTable>,
+    threads: usize,
+    move_overhead: Duration,
+    /// The network from `EvalFile`, used in place of the hand-crafted evaluation with `Use NNUE`
+    network: Option<&'static Network>,
+    use_nnue: bool,
+    /// Castles are written as the king taking its own rook, as `UCI_Chess960` asks
+    chess960: bool,
+    stop: Arc<AtomicBool>,
+    /// Dropped to wake an infinite search that finished early and waits to be told to stop
+    release: Option<Sender<()>>,
+    search: Option<JoinHandle<()>>,
+}
+
+impl Default for Uci {
+    fn default() -> Self {
+        Self {
+            board: AnyBoard::default(),
+            table: Arc::default(),
+            threads: 1,
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+            network: None,
+            use_nnue: false,
+            chess960: false,
+            stop: Arc::new(AtomicBool::new(false)),
+            release: None,
+            search: None,
+        }
+    }
+}
+
+impl Uci {
+    /// Signal the running search to finish and wait for it to print its best move
+    fn stop_search(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.release = None;
+        if let Some(search) = self.search.take() {
+            // A search that died before answering still owes the GUI a best move
+            if search.join().is_err() {
+                println!("bestmove 0000");
+            }
+        }
+    }
+
+    /// The current position, set up to be evaluated as the options say
+    fn evaluated_board(&self) -> AnyBoard {
+        self.board
+            .with_network(self.network.filter(|_| self.use_nnue))
+    }
+
+    fn go(&mut self, go: Go) {
+        self.stop_search();
+        if let Some(depth) = go.perft {
+            // Counting cannot be interrupted, so `stop` and `quit` wait for it, but it runs aside
+            // to keep answering `isready` meanwhile
+            let (board, chess960) = (self.board, self.chess960);
+            self.search = Some(thread::spawn(move || {
+                print_divide(board, depth, chess960);
+            }));
+            return;
+        }
+
+        let board = self.evaluated_board();
+        let limits = go.limits(board.side_to_move(), self.move_overhead);
+        let table = Arc::clone(&self.table);
+        let threads = self.threads;
+        let chess960 = self.chess960.then(|| board.castling_rooks());
+        let stop = Arc::new(AtomicBool::new(false));
+        self.stop = Arc::clone(&stop);
+        let (release, released) = mpsc::channel::<()>();
+        self.release = Some(release);
+        self.search = Some(thread::spawn(move || {
+            let result = search(board, limits, &table, threads, &stop, |info| {
+                print_info(info, chess960)
+            });
+            // Even a finished infinite search must not answer before it is told to stop
+            if go.infinite {
+                // Nothing is ever sent, so this returns once `stop_search` drops the sender
+                let _ = released.recv();
+            }
+            match result.best_move {
+                Some(mv) => println!("bestmove {}", to_uci(mv, chess960)),
+                None => println!("bestmove 0000"),
+            }
+        }));
+    }
+
+    fn set_option(&mut self, name: &str, value: Option<&str>) -> Result<(), UciError> {
+        let number = |name| {
+            value
+                .and_then(|value| value.parse::<usize>().ok())
+                .ok_or(UciError::InvalidValue(name))
+        };
+        let check = |name| match value.map(str::to_ascii_lowercase).as_deref() {
+            Some("true") => Ok(true),
+            Some("false") => Ok(false),
+            _ => Err(UciError::InvalidBool(name)),
+        };
+        if name.eq_ignore_ascii_case("Hash") {
+            let megabytes = number("Hash")?;
+            self.stop_search();
+            self.table = Arc::new(TranspositionTable::new(megabytes.clamp(1, MAX_HASH_MB)));
+        } else if name.eq_ignore_ascii_case("Threads") {
+            self.threads = number("Threads")?.clamp(1, MAX_THREADS);
+        } else if name.eq_ignore_ascii_case("Move Overhead") {
+            let millis = number("Move Overhead")?.min(MAX_MOVE_OVERHEAD_MS);
+            self.move_overhead = Duration::from_millis(millis as u64);
+        } else if name.eq_ignore_ascii_case("EvalFile") {
+            self.network = match value {
+                None | Some("<empty>") => None,
+                Some(path) => Some(Network::load(path)?),
+            };
+            self.use_nnue &= self.network.is_some();
+        } else if name.eq_ignore_ascii_case("Use NNUE") {
+            let use_nnue = check("Use NNUE")?;
+            if use_nnue && self.network.is_none() {
+                return Err(UciError::MissingNetwork);
+            }
+            self.use_nnue = use_nnue;
+        } else if name.eq_ignore_ascii_case("UCI_Chess960") {
+            self.chess960 = check("UCI_Chess960")?;
+        } else {
+            return Err(UciError::UnknownOption(name.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Act on one command, returning false once the engine should exit
+    pub fn execute(&mut self, command: Command) -> Result<bool, UciError> {
+        match command {
+            Command::Uci => {
+                println!(
+                    "id name {} {}",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                );
+                println!("id author the {} developers", env!("CARGO_PKG_NAME"));
+                println!(
+                    "option name Hash type spin default {DEFAULT_HASH_MB} min 1 max {MAX_HASH_MB}"
+                );
+                println!("option name Threads type spin default 1 min 1 max {MAX_THREADS}");
+                println!(
+                    "option name Move Overhead type spin default {} min 0 max {MAX_MOVE_OVERHEAD_MS}",
+                    DEFAULT_MOVE_OVERHEAD.as_millis()
+                );
+                println!("option name EvalFile type string default <empty>");
+                println!("option name Use NNUE type check default false");
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            Command::IsReady => println!("readyok"),
+            Command::UciNewGame => {
+                self.stop_search();
+                self.board = AnyBoard::default();
+                self.table.clear();
+            }
+            Command::Position { board, moves } => self.board = play(*board, &moves, self.chess960)?,
+            Command::Go(go) => self.go(go),
+            Command::Stop => self.stop_search(),
+            Command::Quit => {
+                self.stop_search();
+                return Ok(false);
+            }
+            Command::SetOption { name, value } => self.set_option(&name, value.as_deref())?,
+            Command::Eval { trace: false } => {
+                println!("info string eval cp {}", evaluate(&self.evaluated_board()))
+            }
+            Command::Eval { trace: true } => {
+                println!("{}", trace(&self.board));
+                if self.use_nnue {
+                    let score = evaluate(&self.evaluated_board());
+                    println!("NNUE evaluation: {score:+} (side to move)");
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Speak UCI over `input` and stdout until `quit` or the end of the input
+///
+/// Anything that cannot be understood is reported as an `info string` and otherwise ignored.
+pub fn run(input: impl BufRead) {
+    let mut uci = Uci::default();
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        match Command::parse(&line).map(|command| command.and_then(|c| uci.execute(c))) {
+            None | Some(Ok(true)) => {}
+            Some(Ok(false)) => return,
+            Some(Err(err)) => println!("info string {err}"),
+        }
+    }
+    uci.stop_search();
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fen::{FenError, FenField};
+    use crate::player::Player;
+    use crate::position_builder::PositionError;
+    use crate::search::Limits;
+    use crate::square::Square::A8;
+    use crate::time_management::TimeBudget;
+    use crate::uci::{Command, Go, UciError};
+    use core::time::Duration;
+    use test_case::test_case;
+
+    #[test_case("uci", Command::Uci)]
+    #[test_case("isready", Command::IsReady)]
+    #[test_case("  ucinewgame  ", Command::UciNewGame; "surrounding whitespace")]
+    #[test_case("stop", Command::Stop)]
+    #[test_case("quit", Command::Quit)]
+    #[test_case("eval", Command::Eval { trace: false })]
+    #[test_case("eval trace", Command::Eval { trace: true })]
+    fn parses_simple_commands(line: &str, expected: Command) {
+        assert_eq!(Command::parse(line), Some(Ok(expected)));
+    }
+
+    #[test]
+    fn blank_lines_are_skipped() {
+        assert_eq!(Command::parse("   "), None);
+    }
+
+    #[test]
+    fn parses_positions() {
+        assert_eq!(
+            Command::parse("position startpos"),
+            Some(Ok(Command::Position {
+                board: Box::default(),
+                moves: vec![],
+            }))
+        );
+        assert_eq!(
+            Command::parse("position startpos moves e2e4 e7e5"),
+            Some(Ok(Command::Position {
+                board: Box::default(),
+                moves: vec!["e2e4".parse().unwrap(), "e7e5".parse().unwrap()],
+            }))
+        );
+
+        let fen = "4k3/8/8/8/8/8/8/4K2R w K - 3 40";
+        assert_eq!(
+            Command::parse(&format!("position fen {fen} moves e1g1")),
+            Some(Ok(Command::Position {
+                board: Box::new(fen.parse().unwrap()),
+                moves: vec!["e1g1".parse().unwrap()],
+            }))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_positions() {
+        assert_eq!(
+            Command::parse("position"),
+            Some(Err(UciError::MissingPosition))
+        );
+        assert_eq!(
+            Command::parse("position fen 4k3/8/8/8/8/8/8/4K3 x - - 0 1"),
+            Some(Err(UciError::InvalidFen(FenError {
+                field: FenField::SideToMove,
+                offset: 20,
+            })))
+        );
+        assert!(matches!(
+            Command::parse("position startpos moves e2e4 e7"),
+            Some(Err(UciError::InvalidMove(_)))
+        ));
+        assert_eq!(
+            Command::parse("position startpos e2e4"),
+            Some(Err(UciError::ExpectedMoves("e2e4".to_string())))
+        );
+        assert_eq!(
+            Command::parse("position fen P3k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            Some(Err(UciError::InvalidPosition(
+                PositionError::PawnOnBackRank(A8)
+            )))
+        );
+        assert_eq!(
+            Command::parse("position fen 4k3/4R3/8/8/8/8/8/4K3 w - - 0 1"),
+            Some(Err(UciError::InvalidPosition(
+                PositionError::OpponentInCheck
+            )))
+        );
+    }
+
+    #[test]
+    fn parses_go_limits() {
+        let expected = Go {
+            depth: Some(6),
+            nodes: Some(100_000),
+            movetime: Some(Duration::from_millis(500)),
+            wtime: Some(Duration::from_millis(60_000)),
+            btime: Some(Duration::from_millis(55_000)),
+            winc: Some(Duration::from_millis(1_000)),
+            binc: Some(Duration::from_millis(900)),
+            movestogo: Some(20),
+            ..Go::default()
+        };
+        let line = "go depth 6 nodes 100000 movetime 500 wtime 60000 btime 55000 winc 1000 \
+                    binc 900 movestogo 20 ponder";
+        assert_eq!(Command::parse(line), Some(Ok(Command::Go(expected))));
+
+        let infinite = Go {
+            infinite: true,
+            ..Go::default()
+        };
+        assert_eq!(
+            Command::parse("go infinite"),
+            Some(Ok(Command::Go(infinite)))
+        );
+        assert_eq!(
+            Command::parse("go depth x"),
+            Some(Err(UciError::InvalidValue("depth")))
+        );
+        assert_eq!(
+            Command::parse("go depth 4294967296"),
+            Some(Err(UciError::InvalidValue("depth")))
+        );
+        assert_eq!(
+            Command::parse("go movestogo 4294967296"),
+            Some(Err(UciError::InvalidValue("movestogo")))
+        );
+    }
+
+    #[test]
+    fn go_limits_budget_the_clock() {
+        let overhead = Duration::from_millis(30);
+        let go = Go {
+            depth: Some(4),
+            wtime: Some(Duration::from_millis(60_000)),
+            btime: Some(Duration::from_millis(2_000)),
+            winc: Some(Duration::from_millis(1_000)),
+            movestogo: Some(10),
+            ..Go::default()
+        };
+        let white = go.limits(Player::White, overhead);
+        assert_eq!(white.depth, Some(4));
+        assert_eq!(
+            white.time,
+            Some(TimeBudget::new(
+                Duration::from_millis(60_000),
+                Duration::from_millis(1_000),
+                Some(10),
+                overhead
+            ))
+        );
+        let black = go.limits(Player::Black, overhead).time.unwrap();
+        assert!(black.hard < Duration::from_millis(2_000) - overhead);
+
+        let movetime = Go {
+            movetime: Some(Duration::from_millis(250)),
+            ..go.clone()
+        };
+        let fixed = Duration::from_millis(220);
+        assert_eq!(
+            movetime.limits(Player::Black, overhead).time,
+            Some(TimeBudget {
+                soft: fixed,
+                hard: fixed
+            })
+        );
+
+        let infinite = Go {
+            infinite: true,
+            ..go
+        };
+        assert_eq!(infinite.limits(Player::White, overhead), Limits::default());
+    }
+
+    #[test]
+    fn parses_setoption() {
+        assert_eq!(
+            Command::parse("setoption name Move Overhead value 30"),
+            Some(Ok(Command::SetOption {
+                name: "Move Overhead".to_string(),
+                value: Some("30".to_string()),
+            }))
+        );
+        assert_eq!(
+            Command::parse("setoption name Clear Hash"),
+            Some(Ok(Command::SetOption {
+                name: "Clear Hash".to_string(),
+                value: None,
+            }))
+        );
+        assert_eq!(
+            Command::parse("setoption value 3"),
+            Some(Err(UciError::MissingOptionName))
+        );
+    }
+
+    #[test]
+    fn unknown_commands_are_errors() {
+        assert_eq!(
+            Command::parse("xyzzy"),
+            Some(Err(UciError::UnknownCommand("xyzzy".to_string())))
+        );
+    }
+}
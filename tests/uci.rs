@@ -0,0 +1,231 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+/// Pipe `script` into the engine binary and collect everything it prints before exiting
+fn run_engine(script: &str) -> Vec<String> {
+    let mut engine = Command::new(env!("CARGO_BIN_EXE_magnesium"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to start the engine");
+    engine
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+    let output = engine.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Like [`run_engine`] but keep the input open until the engine prints its best move, so the
+/// search is not cut short by the end of the input
+fn run_until_bestmove(script: &str) -> Vec<String> {
+    let mut engine = Command::new(env!("CARGO_BIN_EXE_magnesium"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to start the engine");
+    let mut stdin = engine.stdin.take().unwrap();
+    stdin.write_all(script.as_bytes()).unwrap();
+
+    let mut output = Vec::new();
+    for line in BufReader::new(engine.stdout.take().unwrap()).lines() {
+        let line = line.unwrap();
+        let done = line.starts_with("bestmove");
+        output.push(line);
+        if done {
+            break;
+        }
+    }
+    stdin.write_all(b"quit\n").unwrap();
+    assert!(engine.wait().unwrap().success());
+
+    output
+}
+
+#[test]
+fn handshake() {
+    let output = run_engine("uci\nisready\nquit\n");
+    assert!(output[0].starts_with("id name magnesium"), "{output:?}");
+    assert!(output.iter().any(|line| line.starts_with("id author")));
+    let uciok = output.iter().position(|line| line == "uciok").unwrap();
+    assert!(output[..uciok]
+        .iter()
+        .any(|line| line.starts_with("option name Hash type spin")));
+    assert!(output[..uciok]
+        .iter()
+        .any(|line| line.starts_with("option name Threads type spin")));
+    assert!(output[..uciok]
+        .iter()
+        .any(|line| line.starts_with("option name Use NNUE type check")));
+    assert!(output[..uciok]
+        .iter()
+        .any(|line| line.starts_with("option name UCI_Chess960 type check")));
+    let readyok = output.iter().position(|line| line == "readyok").unwrap();
+    assert!(uciok < readyok);
+}
+
+#[test]
+fn go_prints_a_legal_best_move() {
+    let output = run_engine("position startpos moves e2e4 e7e5 g1f3\ngo depth 2\nisready\nquit\n");
+    let bestmove = output
+        .iter()
+        .find_map(|line| line.strip_prefix("bestmove "))
+        .unwrap_or_else(|| panic!("no bestmove in {output:?}"));
+    let board = "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        .parse::<magnesium::any_board::AnyBoard>()
+        .unwrap();
+    assert!(board
+        .legal_moves()
+        .find_uci(bestmove.parse().unwrap())
+        .is_some());
+}
+
+#[test]
+fn search_reports_each_depth() {
+    let output = run_until_bestmove("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 3\n");
+    let infos = output
+        .iter()
+        .filter(|line| line.starts_with("info depth"))
+        .collect::<Vec<_>>();
+    assert_eq!(infos.len(), 3, "{output:?}");
+    for (depth, info) in (1..).zip(&infos) {
+        assert!(info.starts_with(&format!("info depth {depth} seldepth ")));
+        for field in [" score ", " nodes ", " nps ", " hashfull ", " time "] {
+            assert!(info.contains(field), "{info}");
+        }
+    }
+    assert!(infos[2].contains(" score mate 1 ") && infos[2].ends_with(" pv a1a8"));
+    assert_eq!(output.last().unwrap(), "bestmove a1a8");
+}
+
+#[test]
+fn searches_on_several_threads() {
+    let output = run_until_bestmove(
+        "setoption name Threads value 4\nposition fen 7k/8/8/8/8/8/R7/1R4K1 w - - 0 1\ngo depth 4\n",
+    );
+    assert!(
+        output.iter().any(|line| line.contains(" score mate 2 ")),
+        "{output:?}"
+    );
+    assert!(output.last().unwrap().starts_with("bestmove "));
+}
+
+#[test]
+fn infinite_search_waits_for_stop() {
+    let output = run_engine("position startpos\ngo infinite\nisready\nstop\nquit\n");
+    let readyok = output.iter().position(|line| line == "readyok").unwrap();
+    let bestmove = output
+        .iter()
+        .position(|line| line.starts_with("bestmove"))
+        .unwrap();
+    assert!(readyok < bestmove, "{output:?}");
+    assert_eq!(
+        output
+            .iter()
+            .filter(|line| line.starts_with("bestmove"))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn go_perft_divides() {
+    let output =
+        run_engine("position fen 8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1\ngo perft 3\nquit\n");
+    // One line per root move, a blank line and the total
+    assert_eq!(output.len(), 14 + 2, "{output:?}");
+    assert_eq!(output.last().unwrap(), "Nodes searched: 2812");
+}
+
+#[test]
+fn chess960_castles_as_king_takes_rook() {
+    let output = run_engine(
+        "setoption name UCI_Chess960 value true
+position fen 1r2k2r/8/8/8/8/8/8/1R2K1R1 w GBhb - 0 1 moves e1g1
+go perft 1
+quit
+",
+    );
+    assert!(output.iter().any(|line| line == "e8b8: 1"), "{output:?}");
+    // The rook that castled to f1 keeps the black king from crossing f8
+    assert!(!output.iter().any(|line| line.starts_with("e8h8")));
+    assert_eq!(output.last().unwrap(), "Nodes searched: 23");
+}
+
+#[test]
+fn refuses_positions_it_cannot_play() {
+    let output = run_engine(
+        "position fen P3k3/8/8/8/8/8/8/4K3 w - - 0 1
+position fen 4k3/4R3/8/8/8/8/8/4K3 w - - 0 1
+position fen 4k3/8/8/3P4/8/8/8/4K3 w - e6 0 1
+go depth 3
+isready
+quit
+",
+    );
+    assert!(output[..3]
+        .iter()
+        .all(|line| line.starts_with("info string")));
+    // The search runs from the last position that was accepted, the starting position
+    assert!(output.iter().any(|line| line.starts_with("bestmove")));
+    assert!(output.iter().any(|line| line == "readyok"));
+}
+
+#[test]
+fn eval_breaks_down_by_term() {
+    let output = run_engine("position startpos\neval\neval trace\nquit\n");
+    assert_eq!(output[0], "info string eval cp 0");
+    assert!(output.iter().any(|line| line.contains("King safety")));
+    assert_eq!(output.last().unwrap(), "Evaluation: +0.00 (white side)");
+}
+
+#[test]
+fn switches_to_a_loaded_network() {
+    // A network of nothing but its output bias evaluates every position the same
+    const NETWORK_BYTES: usize = 2 * (768 * 128 + 128 + 2 * 128 + 1);
+    let mut network = vec![0; NETWORK_BYTES];
+    network[NETWORK_BYTES - 2..].copy_from_slice(&16320i16.to_le_bytes());
+    let path = std::env::temp_dir().join(format!("magnesium-{}.nnue", std::process::id()));
+    std::fs::write(&path, network).unwrap();
+
+    let output = run_engine(&format!(
+        "setoption name Use NNUE value true
+setoption name EvalFile value {}
+         setoption name Use NNUE value true
+position startpos moves e2e4
+eval
+         setoption name Use NNUE value false
+eval
+go depth 2
+isready
+quit
+",
+        path.display()
+    ));
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(output[0], "info string set EvalFile before Use NNUE");
+    assert_eq!(output[1], "info string eval cp 400");
+    assert_ne!(output[2], "info string eval cp 400");
+    assert!(output.iter().any(|line| line.starts_with("bestmove")));
+}
+
+#[test]
+fn reports_bad_input_and_keeps_going() {
+    let output = run_engine(
+        "xyzzy\nposition startpos moves e2e5\nposition fen 8/8 w - - 0 1\nsetoption name Nope value 1\n\
+         setoption name Hash value lots\nsetoption name Hash value 2\nisready\n",
+    );
+    assert_eq!(output.len(), 6, "{output:?}");
+    assert!(output[..5]
+        .iter()
+        .all(|line| line.starts_with("info string")));
+    assert_eq!(output[5], "readyok");
+}